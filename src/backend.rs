@@ -0,0 +1,474 @@
+use async_trait::async_trait;
+use parity_scale_codec::{Decode, DecodeAll, Encode};
+use scale_info::TypeDef;
+use sp_core::{sr25519, H256};
+use sp_runtime::AccountId32;
+use std::{collections::HashMap, sync::OnceLock};
+use subxt::{
+  blocks::ExtrinsicEvents,
+  error::{RpcError, TransactionError},
+  ext::frame_metadata::RuntimeMetadataPrefixed,
+  tx::{PairSigner, TxPayload, TxStatus},
+  Metadata, PolkadotConfig,
+};
+
+use crate::{
+  finalbiome::runtime_types, BlockAt, Client, EventReport, ImpexError, Rejection, ResultOf,
+};
+
+/// Metadata of the runtime the utility is built for
+const METADATA: &[u8] = include_bytes!("../artifacts/finalbiome_metadata.scale");
+
+/// Call of any pallet of the runtime
+pub type RuntimeCall = runtime_types::finalbiome_node_runtime::Call;
+/// Event of any pallet of the runtime
+pub type RuntimeEvent = runtime_types::finalbiome_node_runtime::Event;
+
+/// Call included in a block with the events it emitted
+#[derive(Clone, Debug)]
+pub struct SubmittedCall {
+  /// Hash of the block in which the call was included
+  pub block_hash: H256,
+  /// Hash of the extrinsic
+  pub extrinsic_hash: H256,
+  /// Events emitted by the call
+  pub events: Vec<RuntimeEvent>,
+  /// Events with their decoded fields, including the events of the pallets which are not in
+  /// the runtime metadata of the crate, e.g. `Utility`
+  pub reports: Vec<EventReport>,
+}
+
+/// Chain from which the games are fetched and to which the calls are submitted.
+///
+/// The storage is read by the raw keys, so the fetching of the game does not depend on the
+/// backend. Implemented by the subxt client of the node and, with the `testing` feature, by `MemoryChain`.
+#[async_trait]
+pub trait ChainBackend: Send + Sync {
+  /// Returns the hash of the given block, fails if the block is unknown
+  async fn block_hash(&self, at: &BlockAt) -> ResultOf<H256>;
+
+  /// Returns the number of the block with the given hash
+  async fn block_number(&self, hash: H256) -> ResultOf<u64>;
+
+  /// Returns the version of the node
+  async fn node_version(&self) -> ResultOf<String>;
+
+  /// Returns up to `count` storage keys with the given prefix which follow `start_key`
+  async fn fetch_keys(
+    &self,
+    prefix: &[u8],
+    count: u32,
+    start_key: Option<&[u8]>,
+    at: H256,
+  ) -> ResultOf<Vec<Vec<u8>>>;
+
+  /// Returns the encoded values of the storage keys, `None` for the keys without value
+  async fn fetch_values(&self, keys: &[Vec<u8>], at: H256) -> ResultOf<Vec<Option<Vec<u8>>>>;
+
+  /// Returns the nonce of the next transaction of the account
+  async fn account_nonce(&self, account: &AccountId32) -> ResultOf<u32>;
+
+  /// Signs the call, submits it and waits until it's included in a block.
+  ///
+  /// The call is signed with the given nonce, or with the next nonce of the signer if not set.
+  /// A call failed in the runtime is returned as [`ImpexError::Dispatch`], a transaction not
+  /// accepted or dropped by the transaction pool as [`ImpexError::Rejected`].
+  async fn submit(
+    &self,
+    signer: &sr25519::Pair,
+    call: RuntimeCall,
+    nonce: Option<u32>,
+  ) -> ResultOf<SubmittedCall>;
+
+  /// Returns true if the runtime can dispatch the calls in a batch with `Utility::batch_all`
+  async fn supports_batch(&self) -> ResultOf<bool>;
+
+  /// Submits the calls in one `Utility::batch_all` extrinsic and waits until it's included in a
+  /// block.
+  ///
+  /// The batch is atomic, if one of the calls fails, none of them is dispatched. Returns the
+  /// result of each call with the events it emitted.
+  async fn submit_batch(
+    &self,
+    signer: &sr25519::Pair,
+    calls: Vec<RuntimeCall>,
+    nonce: Option<u32>,
+  ) -> ResultOf<Vec<SubmittedCall>>;
+}
+
+#[async_trait]
+impl ChainBackend for Client {
+  async fn block_hash(&self, at: &BlockAt) -> ResultOf<H256> {
+    match at {
+      BlockAt::Best => self
+        .rpc()
+        .block_hash(None)
+        .await?
+        .ok_or_else(|| ImpexError::NotFound("Cannot fetch current hash".to_string())),
+      BlockAt::Finalized => Ok(self.rpc().finalized_head().await?),
+      BlockAt::Number(number) => self
+        .rpc()
+        .block_hash(Some((*number).into()))
+        .await?
+        .ok_or_else(|| ImpexError::NotFound(format!("Block {} not found", number))),
+      BlockAt::Hash(hex) => {
+        let hash = parse_block_hash(hex)?;
+        match self.rpc().header(Some(hash)).await? {
+          Some(_) => Ok(hash),
+          None => Err(ImpexError::NotFound(format!("Block {} not found", hex))),
+        }
+      },
+    }
+  }
+
+  async fn block_number(&self, hash: H256) -> ResultOf<u64> {
+    let header = self
+      .rpc()
+      .header(Some(hash))
+      .await?
+      .ok_or_else(|| ImpexError::NotFound("Cannot fetch header of the block".to_string()))?;
+    Ok(header.number.into())
+  }
+
+  async fn node_version(&self) -> ResultOf<String> {
+    Ok(self.rpc().system_version().await?)
+  }
+
+  async fn fetch_keys(
+    &self,
+    prefix: &[u8],
+    count: u32,
+    start_key: Option<&[u8]>,
+    at: H256,
+  ) -> ResultOf<Vec<Vec<u8>>> {
+    let keys = self
+      .storage()
+      .fetch_keys(prefix, count, start_key, Some(at))
+      .await?;
+    Ok(keys.into_iter().map(|key| key.0).collect())
+  }
+
+  async fn fetch_values(&self, keys: &[Vec<u8>], at: H256) -> ResultOf<Vec<Option<Vec<u8>>>> {
+    let change_sets = self
+      .rpc()
+      .query_storage_at(keys.iter().map(|key| &key[..]), Some(at))
+      .await?;
+    let mut data: HashMap<Vec<u8>, Vec<u8>> = HashMap::new();
+    for change_set in change_sets {
+      for (key, value) in change_set.changes {
+        if let Some(value) = value {
+          data.insert(key.0, value.0);
+        }
+      }
+    }
+    Ok(keys.iter().map(|key| data.remove(key)).collect())
+  }
+
+  async fn account_nonce(&self, account: &AccountId32) -> ResultOf<u32> {
+    Ok(self.rpc().system_account_next_index(account).await?)
+  }
+
+  async fn submit(
+    &self,
+    signer: &sr25519::Pair,
+    call: RuntimeCall,
+    nonce: Option<u32>,
+  ) -> ResultOf<SubmittedCall> {
+    check_call(&self.metadata(), &call)?;
+    let extrinsic_events = submit_payload(self, signer, &EncodedCall(call), nonce).await?;
+    let (events, reports): (Vec<_>, Vec<_>) =
+      collect_events(&extrinsic_events)?.into_iter().unzip();
+    Ok(submitted_call(
+      &extrinsic_events,
+      events.into_iter().flatten().collect(),
+      reports,
+    ))
+  }
+
+  async fn supports_batch(&self) -> ResultOf<bool> {
+    Ok(batch_all_index(&self.metadata()).is_some())
+  }
+
+  async fn submit_batch(
+    &self,
+    signer: &sr25519::Pair,
+    calls: Vec<RuntimeCall>,
+    nonce: Option<u32>,
+  ) -> ResultOf<Vec<SubmittedCall>> {
+    let index = batch_all_index(&self.metadata()).ok_or_else(|| {
+      ImpexError::InvalidArgument("Runtime has no Utility::batch_all call".to_string())
+    })?;
+    let metadata = self.metadata();
+    for call in &calls {
+      check_call(&metadata, call)?;
+    }
+    let count = calls.len();
+    let extrinsic_events = submit_payload(self, signer, &BatchCall { index, calls }, nonce).await?;
+    // the events of each call are followed by the `ItemCompleted` event
+    let mut items = vec![(vec![], vec![])];
+    for (event, report) in collect_events(&extrinsic_events)? {
+      if report.pallet == "Utility" {
+        if report.variant == "ItemCompleted" {
+          items.push((vec![], vec![]));
+        }
+        continue;
+      }
+      let (events, reports) = items.last_mut().expect("items are not empty");
+      events.extend(event);
+      reports.push(report);
+    }
+    // the events of the extrinsic itself, e.g. the fee payment, are reported with the last call
+    let (events, reports) = items.pop().expect("items are not empty");
+    match items.last_mut() {
+      Some(last) => {
+        last.0.extend(events);
+        last.1.extend(reports);
+      },
+      None => items.push((events, reports)),
+    }
+    if items.len() != count {
+      return Err(ImpexError::Decode(format!(
+        "Batch of {} calls completed {} items",
+        count,
+        items.len()
+      )));
+    }
+    Ok(
+      items
+        .into_iter()
+        .map(|(events, reports)| submitted_call(&extrinsic_events, events, reports))
+        .collect(),
+    )
+  }
+}
+
+/// Signs the payload, submits it and waits for its successful inclusion in a block
+async fn submit_payload<P: TxPayload>(
+  api: &Client,
+  signer: &sr25519::Pair,
+  payload: &P,
+  nonce: Option<u32>,
+) -> ResultOf<ExtrinsicEvents<PolkadotConfig>> {
+  let signer = PairSigner::new(signer.clone());
+  let mut progress = match nonce {
+    Some(nonce) => {
+      api
+        .tx()
+        .create_signed_with_nonce(payload, &signer, nonce, Default::default())?
+        .submit_and_watch()
+        .await?
+    },
+    None => {
+      api
+        .tx()
+        .sign_and_submit_then_watch_default(payload, &signer)
+        .await?
+    },
+  };
+  // unlike `wait_for_in_block`, the transactions removed from the pool are reported
+  while let Some(status) = progress.next_item().await {
+    match status? {
+      TxStatus::InBlock(in_block) | TxStatus::Finalized(in_block) => {
+        return Ok(in_block.wait_for_success().await?)
+      },
+      TxStatus::Usurped(_) | TxStatus::Dropped | TxStatus::Invalid => {
+        return Err(ImpexError::Rejected(Rejection::Dropped))
+      },
+      TxStatus::FinalityTimeout(_) => {
+        return Err(subxt::Error::from(TransactionError::FinalitySubscriptionTimeout).into())
+      },
+      _ => continue,
+    }
+  }
+  Err(subxt::Error::from(RpcError::SubscriptionDropped).into())
+}
+
+/// Metadata of the runtime the utility is built for, decoded once
+pub(crate) fn bundled_metadata() -> ResultOf<&'static Metadata> {
+  static BUNDLED: OnceLock<Metadata> = OnceLock::new();
+  if let Some(metadata) = BUNDLED.get() {
+    return Ok(metadata);
+  }
+  let prefixed = RuntimeMetadataPrefixed::decode(&mut &*METADATA)?;
+  let metadata = Metadata::try_from(prefixed)?;
+  Ok(BUNDLED.get_or_init(|| metadata))
+}
+
+/// Checks that the node has the call with the same indices and the same types as the runtime the
+/// utility is built for.
+///
+/// The calls are encoded with the indices of the bundled metadata and are not validated by
+/// subxt, so a node with another runtime could dispatch a different call.
+fn check_call(node: &Metadata, call: &RuntimeCall) -> ResultOf<()> {
+  let encoded = call.encode();
+  let (pallet_index, call_index) = match encoded[..] {
+    [pallet_index, call_index, ..] => (pallet_index, call_index),
+    _ => {
+      return Err(ImpexError::Decode(
+        "Call is encoded without its index".to_string(),
+      ))
+    },
+  };
+  let bundled = bundled_metadata()?;
+  let runtime = bundled.runtime_metadata();
+  let names = runtime
+    .pallets
+    .iter()
+    .find(|pallet| pallet.index == pallet_index)
+    .and_then(|pallet| {
+      let calls = runtime.types.resolve(pallet.calls.as_ref()?.ty.id())?;
+      let TypeDef::Variant(variants) = calls.type_def() else {
+        return None;
+      };
+      let variant = variants
+        .variants()
+        .iter()
+        .find(|variant| variant.index() == call_index)?;
+      Some((pallet.name.as_str(), variant.name().as_str()))
+    });
+  let Some((pallet, name)) = names else {
+    return Err(ImpexError::Decode(format!(
+      "Call {}:{} is not in the runtime the utility is built for",
+      pallet_index, call_index
+    )));
+  };
+  let compatible = node.pallet(pallet).is_ok_and(|node_pallet| {
+    node_pallet.index() == pallet_index
+      && node_pallet.call_index(name).ok() == Some(call_index)
+      && matches!(
+        (node.call_hash(pallet, name), bundled.call_hash(pallet, name)),
+        (Ok(node_hash), Ok(hash)) if node_hash == hash
+      )
+  });
+  if !compatible {
+    return Err(ImpexError::Decode(format!(
+      "Call {}.{} of the node differs from the runtime the utility is built for",
+      pallet, name
+    )));
+  }
+  Ok(())
+}
+
+/// Pallet and call index of `Utility::batch_all` if the runtime has it
+fn batch_all_index(metadata: &Metadata) -> Option<(u8, u8)> {
+  let pallet = metadata.pallet("Utility").ok()?;
+  Some((pallet.index(), pallet.call_index("batch_all").ok()?))
+}
+
+/// Parse the 0x-prefixed hash of the block
+pub(crate) fn parse_block_hash(hex: &str) -> ResultOf<H256> {
+  let invalid_hash = |e: &dyn std::fmt::Display| {
+    ImpexError::InvalidArgument(format!("Invalid block hash {}: {}", hex, e))
+  };
+  let bytes = sp_core::bytes::from_hex(hex).map_err(|e| invalid_hash(&e))?;
+  H256::decode_all(&mut &bytes[..]).map_err(|e| invalid_hash(&e))
+}
+
+/// Collect the events of the extrinsic submitted to the node.
+///
+/// The events of the pallets which are unknown to the runtime metadata of the crate are only
+/// reported.
+fn collect_events<T>(
+  events: &ExtrinsicEvents<T>,
+) -> ResultOf<Vec<(Option<RuntimeEvent>, EventReport)>>
+where
+  T: subxt::Config<Hash = H256>,
+{
+  let mut collected = vec![];
+  for event in events.iter() {
+    let event = event?;
+    let report = EventReport {
+      pallet: event.pallet_name().to_string(),
+      variant: event.variant_name().to_string(),
+      fields: event.field_values()?.to_string(),
+    };
+    collected.push((event.as_root_event::<RuntimeEvent>().ok(), report));
+  }
+  Ok(collected)
+}
+
+/// Call of the extrinsic with the given events
+fn submitted_call<T>(
+  extrinsic_events: &ExtrinsicEvents<T>,
+  events: Vec<RuntimeEvent>,
+  reports: Vec<EventReport>,
+) -> SubmittedCall
+where
+  T: subxt::Config<Hash = H256>,
+{
+  SubmittedCall {
+    block_hash: extrinsic_events.block_hash(),
+    extrinsic_hash: extrinsic_events.extrinsic_hash(),
+    events,
+    reports,
+  }
+}
+
+/// Runtime call submitted as is, it's checked against the node metadata by [`check_call`]
+struct EncodedCall(RuntimeCall);
+
+impl TxPayload for EncodedCall {
+  fn encode_call_data_to(
+    &self,
+    _metadata: &Metadata,
+    out: &mut Vec<u8>,
+  ) -> Result<(), subxt::Error> {
+    self.0.encode_to(out);
+    Ok(())
+  }
+}
+
+/// `Utility::batch_all` with the calls of the runtime, encoded by the indices of the node metadata.
+/// The batched calls are checked one by one by [`check_call`].
+struct BatchCall {
+  index: (u8, u8),
+  calls: Vec<RuntimeCall>,
+}
+
+impl TxPayload for BatchCall {
+  fn encode_call_data_to(
+    &self,
+    _metadata: &Metadata,
+    out: &mut Vec<u8>,
+  ) -> Result<(), subxt::Error> {
+    self.index.encode_to(out);
+    self.calls.encode_to(out);
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use subxt::ext::frame_metadata::RuntimeMetadata;
+
+  use super::*;
+  use runtime_types::pallet_organization_identity::pallet::Call as OrganizationCall;
+
+  fn add_member() -> RuntimeCall {
+    RuntimeCall::OrganizationIdentity(OrganizationCall::add_member {
+      who: AccountId32::new([1; 32]),
+    })
+  }
+
+  #[test]
+  fn call_of_the_same_runtime_is_checked() {
+    check_call(bundled_metadata().unwrap(), &add_member()).unwrap();
+  }
+
+  #[test]
+  fn call_of_another_runtime_is_not_submitted() {
+    // the node has the pallet at another index, so the encoded call would dispatch another one
+    let mut prefixed = RuntimeMetadataPrefixed::decode(&mut &*METADATA).unwrap();
+    let RuntimeMetadata::V14(runtime) = &mut prefixed.1 else {
+      panic!("metadata is not V14");
+    };
+    for pallet in &mut runtime.pallets {
+      if pallet.name == "OrganizationIdentity" {
+        pallet.index += 100;
+      }
+    }
+    let node = Metadata::try_from(prefixed).unwrap();
+    let result = check_call(&node, &add_member());
+    assert!(matches!(result, Err(ImpexError::Decode(_))), "{:?}", result);
+  }
+}
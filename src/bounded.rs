@@ -0,0 +1,121 @@
+use parity_scale_codec::{Decode, Encode};
+use serde::{
+  de::{self, SeqAccess, Visitor},
+  Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::{any::Any, fmt, marker::PhantomData};
+
+/// Substitute of the runtime `BoundedVec` with the same SCALE encoding.
+///
+/// Bytes (names, attribute keys and text values) are serialized as UTF-8 strings, or as `0x`-hex
+/// if they are not valid UTF-8 or the string itself starts with `0x`. Arrays of numbers written
+/// by the previous versions are still accepted.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug, Default)]
+pub struct BoundedVec<T>(pub Vec<T>);
+
+impl<T> Serialize for BoundedVec<T>
+where
+  T: Serialize + 'static,
+{
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    match (&self.0 as &dyn Any).downcast_ref::<Vec<u8>>() {
+      Some(bytes) => match std::str::from_utf8(bytes) {
+        Ok(s) if !s.starts_with("0x") => serializer.serialize_str(s),
+        _ => serializer.serialize_str(&sp_core::bytes::to_hex(bytes, false)),
+      },
+      None => self.0.serialize(serializer),
+    }
+  }
+}
+
+impl<'de, T> Deserialize<'de> for BoundedVec<T>
+where
+  T: Deserialize<'de> + 'static,
+{
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    let items = deserializer.deserialize_any(ItemsVisitor(PhantomData))?;
+    Ok(BoundedVec(items))
+  }
+}
+
+/// Accepts a sequence of items or, if the items are bytes, a string
+struct ItemsVisitor<T>(PhantomData<T>);
+
+impl<'de, T> Visitor<'de> for ItemsVisitor<T>
+where
+  T: Deserialize<'de> + 'static,
+{
+  type Value = Vec<T>;
+
+  fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+    formatter.write_str("a sequence or a string")
+  }
+
+  fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+    let mut items = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+    while let Some(item) = seq.next_element()? {
+      items.push(item);
+    }
+    Ok(items)
+  }
+
+  fn visit_str<E: de::Error>(self, s: &str) -> Result<Self::Value, E> {
+    // a hand written string which starts with `0x` but is not hex is taken as is
+    let bytes = match s.starts_with("0x") {
+      true => sp_core::bytes::from_hex(s).unwrap_or_else(|_| s.as_bytes().to_vec()),
+      false => s.as_bytes().to_vec(),
+    };
+    // only the bytes can be represented as a string
+    let items: Box<dyn Any> = Box::new(bytes);
+    items
+      .downcast::<Vec<T>>()
+      .map(|items| *items)
+      .map_err(|_| E::invalid_type(de::Unexpected::Str(s), &self))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn to_json(bytes: &[u8]) -> String {
+    serde_json::to_string(&BoundedVec(bytes.to_vec())).unwrap()
+  }
+
+  fn from_json(json: &str) -> Vec<u8> {
+    serde_json::from_str::<BoundedVec<u8>>(json).unwrap().0
+  }
+
+  #[test]
+  fn utf8_bytes_are_written_as_string() {
+    assert_eq!(to_json(b"Gold Coin"), r#""Gold Coin""#);
+    assert_eq!(from_json(r#""Gold Coin""#), b"Gold Coin");
+  }
+
+  #[test]
+  fn other_bytes_are_written_as_hex() {
+    assert_eq!(to_json(&[0xff, 0x00]), r#""0xff00""#);
+    assert_eq!(from_json(r#""0xff00""#), [0xff, 0x00]);
+  }
+
+  #[test]
+  fn string_starting_with_0x_round_trips() {
+    let json = to_json(b"0xcafe");
+    assert_eq!(json, r#""0x307863616665""#);
+    assert_eq!(from_json(&json), b"0xcafe");
+    // a hand written string which is not hex is taken as is
+    assert_eq!(from_json(r#""0x not hex""#), b"0x not hex");
+  }
+
+  #[test]
+  fn legacy_array_of_numbers_is_accepted() {
+    assert_eq!(from_json("[71, 111, 108, 100]"), b"Gold");
+  }
+
+  #[test]
+  fn other_items_are_written_as_array() {
+    let items = BoundedVec(vec![1u32, 2]);
+    assert_eq!(serde_json::to_string(&items).unwrap(), "[1,2]");
+    assert!(serde_json::from_str::<BoundedVec<u32>>(r#""12""#).is_err());
+  }
+}
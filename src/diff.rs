@@ -0,0 +1,433 @@
+use serde::Serialize;
+use std::{collections::HashMap, fmt};
+
+use crate::{
+  finalbiome::runtime_types::pallet_support::characteristics::Characteristic,
+  game_spec::GameSpec,
+  plan::{remap_characteristic, remap_onboarding_assets},
+  FungibleAssetId, NonFungibleClassId,
+};
+
+/// Kind of the difference between two game specs
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffKind {
+  Added,
+  Removed,
+  Changed,
+}
+
+/// A single difference between two game specs
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct DiffEntry {
+  pub kind: DiffKind,
+  /// Kind of the item, e.g. `member`, `fa`, `attribute`
+  pub item: String,
+  /// Name of the item
+  pub name: String,
+  /// Value in the first spec, serialized to JSON
+  pub from: Option<String>,
+  /// Value in the second spec, serialized to JSON
+  pub to: Option<String>,
+}
+
+/// Semantic differences between two game specs
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct SpecDiff {
+  pub entries: Vec<DiffEntry>,
+}
+
+impl SpecDiff {
+  /// Returns true if the specs are equal
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+
+  fn push<T: Serialize>(
+    &mut self,
+    kind: DiffKind,
+    item: &str,
+    name: String,
+    from: Option<&T>,
+    to: Option<&T>,
+  ) {
+    let to_json = |value: &T| serde_json::to_string(value).unwrap_or_default();
+    self.entries.push(DiffEntry {
+      kind,
+      item: item.to_string(),
+      name,
+      from: from.map(to_json),
+      to: to.map(to_json),
+    });
+  }
+}
+
+impl fmt::Display for SpecDiff {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for entry in &self.entries {
+      let sign = match entry.kind {
+        DiffKind::Added => "+",
+        DiffKind::Removed => "-",
+        DiffKind::Changed => "~",
+      };
+      write!(f, "{} {}", sign, entry.item)?;
+      if !entry.name.is_empty() {
+        write!(f, " {}", entry.name)?;
+      }
+      match (&entry.from, &entry.to) {
+        (Some(from), Some(to)) => writeln!(f, ": {} -> {}", from, to)?,
+        (Some(value), None) | (None, Some(value)) => writeln!(f, ": {}", value)?,
+        (None, None) => writeln!(f)?,
+      }
+    }
+    Ok(())
+  }
+}
+
+/// Readable representation of the name stored as bytes
+fn name_of(bytes: &[u8]) -> String {
+  format!("{:?}", String::from_utf8_lossy(bytes))
+}
+
+impl GameSpec {
+  /// Compares the game spec with another one.
+  ///
+  /// Assets are matched by name, so the specs exported from different networks can be compared.
+  pub fn diff(&self, other: &GameSpec) -> SpecDiff {
+    let mut diff = SpecDiff::default();
+
+    // 1. Organization
+    let org_name = &self.organization_details.name.0;
+    let other_org_name = &other.organization_details.name.0;
+    if org_name != other_org_name {
+      diff.push(
+        DiffKind::Changed,
+        "organization",
+        "name".to_string(),
+        Some(&name_of(org_name)),
+        Some(&name_of(other_org_name)),
+      );
+    }
+
+    // 2. Members
+    for member in &self.organization_members {
+      if !other.organization_members.contains(member) {
+        diff.push::<()>(DiffKind::Removed, "member", member.to_string(), None, None);
+      }
+    }
+    for member in &other.organization_members {
+      if !self.organization_members.contains(member) {
+        diff.push::<()>(DiffKind::Added, "member", member.to_string(), None, None);
+      }
+    }
+
+    // 3. FA
+    let mut fa_ids_map: HashMap<FungibleAssetId, FungibleAssetId> = HashMap::new();
+    for (fa, details) in &self.fa {
+      let name = name_of(&details.name.0);
+      match other.fa.iter().find(|(_, d)| d.name == details.name) {
+        Some((other_fa, other_details)) => {
+          fa_ids_map.insert(*fa, *other_fa);
+          if details.top_upped != other_details.top_upped {
+            diff.push(
+              DiffKind::Changed,
+              "fa",
+              format!("{}.top_upped", name),
+              Some(&details.top_upped),
+              Some(&other_details.top_upped),
+            );
+          }
+          if details.cup_global != other_details.cup_global {
+            diff.push(
+              DiffKind::Changed,
+              "fa",
+              format!("{}.cup_global", name),
+              Some(&details.cup_global),
+              Some(&other_details.cup_global),
+            );
+          }
+          if details.cup_local != other_details.cup_local {
+            diff.push(
+              DiffKind::Changed,
+              "fa",
+              format!("{}.cup_local", name),
+              Some(&details.cup_local),
+              Some(&other_details.cup_local),
+            );
+          }
+        },
+        None => diff.push::<()>(DiffKind::Removed, "fa", name, None, None),
+      }
+    }
+    for (_, details) in &other.fa {
+      if !self.fa.iter().any(|(_, d)| d.name == details.name) {
+        diff.push::<()>(DiffKind::Added, "fa", name_of(&details.name.0), None, None);
+      }
+    }
+
+    // 4. NFA
+    let mut nfa_ids_map: HashMap<NonFungibleClassId, NonFungibleClassId> = HashMap::new();
+    for (class, details) in &self.nfa {
+      match other.nfa.iter().find(|(_, d)| d.name == details.name) {
+        Some((other_class, _)) => {
+          nfa_ids_map.insert(*class, *other_class);
+        },
+        None => diff.push::<()>(
+          DiffKind::Removed,
+          "nfa",
+          name_of(&details.name.0),
+          None,
+          None,
+        ),
+      }
+    }
+    for (_, details) in &other.nfa {
+      if !self.nfa.iter().any(|(_, d)| d.name == details.name) {
+        diff.push::<()>(DiffKind::Added, "nfa", name_of(&details.name.0), None, None);
+      }
+    }
+    for (class, details) in &self.nfa {
+      let Some(other_class) = nfa_ids_map.get(class) else {
+        continue;
+      };
+      let Some(other_details) = other.get_nfa(*other_class) else {
+        continue;
+      };
+      let name = name_of(&details.name.0);
+
+      // 4.1 Attributes
+      let attrs: Vec<_> = self
+        .attributes
+        .iter()
+        .filter(|(c, ..)| c == class)
+        .collect();
+      let other_attrs: Vec<_> = other
+        .attributes
+        .iter()
+        .filter(|(c, ..)| c == other_class)
+        .collect();
+      for (_, key, value) in &attrs {
+        let attr_name = format!("{}.{}", name, name_of(&key.0));
+        match other_attrs.iter().find(|(_, k, _)| k == key) {
+          Some((_, _, other_value)) if other_value != value => diff.push(
+            DiffKind::Changed,
+            "attribute",
+            attr_name,
+            Some(value),
+            Some(other_value),
+          ),
+          Some(_) => (),
+          None => diff.push(DiffKind::Removed, "attribute", attr_name, Some(value), None),
+        }
+      }
+      for (_, key, value) in &other_attrs {
+        if !attrs.iter().any(|(_, k, _)| k == key) {
+          let attr_name = format!("{}.{}", name, name_of(&key.0));
+          diff.push(DiffKind::Added, "attribute", attr_name, None, Some(value));
+        }
+      }
+
+      // 4.2 Characteristics, ids of the assets are compared after matching by name
+      let characteristics = [
+        (
+          "bettor",
+          Characteristic::Bettor(details.bettor.clone()),
+          Characteristic::Bettor(other_details.bettor.clone()),
+        ),
+        (
+          "purchased",
+          Characteristic::Purchased(details.purchased.clone()),
+          Characteristic::Purchased(other_details.purchased.clone()),
+        ),
+      ];
+      for (item, characteristic, other_characteristic) in characteristics {
+        let remapped = remap_characteristic(characteristic.clone(), &fa_ids_map, &nfa_ids_map);
+        // dangling references (see `GameSpec::validate`) cannot be remapped, compare them as is
+        let same = match &remapped {
+          Some(remapped) => remapped == &other_characteristic,
+          None => characteristic == other_characteristic,
+        };
+        if same {
+          continue;
+        }
+        let kind = match (&characteristic, &other_characteristic) {
+          (Characteristic::Bettor(None), _) | (Characteristic::Purchased(None), _) => {
+            DiffKind::Added
+          },
+          (_, Characteristic::Bettor(None)) | (_, Characteristic::Purchased(None)) => {
+            DiffKind::Removed
+          },
+          _ => DiffKind::Changed,
+        };
+        diff.push(
+          kind,
+          item,
+          name.clone(),
+          Some(&characteristic),
+          Some(&other_characteristic),
+        );
+      }
+    }
+
+    // 5. Onboarding assets
+    let onboarding = self
+      .organization_details
+      .onboarding_assets
+      .clone()
+      .map(|assets| assets.0)
+      .unwrap_or_default();
+    let other_onboarding = other
+      .organization_details
+      .onboarding_assets
+      .clone()
+      .map(|assets| assets.0)
+      .unwrap_or_default();
+    let remapped = remap_onboarding_assets(onboarding.clone(), &fa_ids_map, &nfa_ids_map);
+    let same = match &remapped {
+      Some(remapped) => remapped == &other_onboarding,
+      // dangling references cannot be remapped, compare them as is
+      None => onboarding == other_onboarding,
+    };
+    if !same {
+      diff.push(
+        DiffKind::Changed,
+        "onboarding_assets",
+        "".to_string(),
+        Some(&onboarding),
+        Some(&other_onboarding),
+      );
+    }
+
+    diff
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use super::*;
+  use crate::game_spec::tests::game_spec;
+
+  fn entries(diff: &SpecDiff) -> Vec<(DiffKind, &str, &str)> {
+    diff
+      .entries
+      .iter()
+      .map(|entry| (entry.kind, entry.item.as_str(), entry.name.as_str()))
+      .collect()
+  }
+
+  #[test]
+  fn assets_are_matched_by_name() {
+    // the order of the assets is changed, so they get other ids
+    let spec = game_spec(|_| {});
+    let other = game_spec(|content| {
+      content["fa"].as_array_mut().unwrap().reverse();
+      content["nfa"].as_array_mut().unwrap().reverse();
+    });
+    assert_ne!(spec.fa[0].0, other.fa[1].0);
+    assert!(spec.diff(&other).is_empty());
+  }
+
+  #[test]
+  fn references_are_compared_after_matching_by_name() {
+    // after the reordering energy gets the id that gold had before
+    let other = game_spec(|content| {
+      content["fa"].as_array_mut().unwrap().reverse();
+      content["nfa"][0][1]["purchased"]["offers"][0]["fa"] = json!("energy");
+      content["organization_details"]["onboarding_assets"] =
+        json!([{ "Fa": ["energy", 5] }, { "Nfa": ["sword", []] }]);
+    });
+    let diff = game_spec(|_| {}).diff(&other);
+    assert_eq!(
+      entries(&diff),
+      vec![
+        (DiffKind::Changed, "purchased", "\"Sword\""),
+        (DiffKind::Changed, "onboarding_assets", ""),
+      ]
+    );
+  }
+
+  #[test]
+  fn members_are_added_and_removed() {
+    let other = game_spec(|content| {
+      content["organization_members"] = json!(["5FLSigC9HGRKVhB9FiEo4Y3koPsNmBmLJbpXg2mp1hXcS59Y"]);
+    });
+    let diff = game_spec(|_| {}).diff(&other);
+    assert_eq!(
+      entries(&diff),
+      vec![
+        (
+          DiffKind::Removed,
+          "member",
+          "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty"
+        ),
+        (
+          DiffKind::Added,
+          "member",
+          "5FLSigC9HGRKVhB9FiEo4Y3koPsNmBmLJbpXg2mp1hXcS59Y"
+        ),
+      ]
+    );
+  }
+
+  #[test]
+  fn assets_are_changed() {
+    let other = game_spec(|content| {
+      content["fa"][0][1]["cup_global"] = json!({ "amount": 30 });
+      content["fa"][1][1]["name"] = json!("Mana");
+      content["nfa"][1][1]["name"] = json!("Bow");
+    });
+    let diff = game_spec(|_| {}).diff(&other);
+    assert_eq!(
+      entries(&diff),
+      vec![
+        (DiffKind::Changed, "fa", "\"Gold\".cup_global"),
+        (DiffKind::Removed, "fa", "\"Energy\""),
+        (DiffKind::Added, "fa", "\"Mana\""),
+        (DiffKind::Removed, "nfa", "\"Shield\""),
+        (DiffKind::Added, "nfa", "\"Bow\""),
+      ]
+    );
+    assert_eq!(diff.entries[0].from.as_deref(), Some(r#"{"amount":20}"#));
+    assert_eq!(diff.entries[0].to.as_deref(), Some(r#"{"amount":30}"#));
+  }
+
+  #[test]
+  fn attributes_and_characteristics_are_changed() {
+    let other = game_spec(|content| {
+      content["attributes"] = json!([
+        ["sword", "damage", { "Number": { "number_value": 3, "number_max": null } }],
+        ["sword", "weight", { "Text": "heavy" }],
+      ]);
+      content["nfa"][0][1]["purchased"]["offers"][0]["price"] = json!(20);
+      content["nfa"][1][1]["purchased"] =
+        json!({ "offers": [{ "fa": "energy", "price": 1, "attributes": [] }] });
+      content["organization_details"]["onboarding_assets"] = json!([{ "Fa": ["gold", 10] }]);
+    });
+    let diff = game_spec(|_| {}).diff(&other);
+    assert_eq!(
+      entries(&diff),
+      vec![
+        (DiffKind::Changed, "attribute", "\"Sword\".\"damage\""),
+        (DiffKind::Removed, "attribute", "\"Sword\".\"rarity\""),
+        (DiffKind::Added, "attribute", "\"Sword\".\"weight\""),
+        (DiffKind::Changed, "purchased", "\"Sword\""),
+        (DiffKind::Added, "purchased", "\"Shield\""),
+        (DiffKind::Changed, "onboarding_assets", ""),
+      ]
+    );
+  }
+
+  #[test]
+  fn characteristic_is_removed() {
+    let other = game_spec(|content| {
+      content["nfa"][0][1]["purchased"] = json!(null);
+    });
+    let diff = game_spec(|_| {}).diff(&other);
+    assert_eq!(
+      entries(&diff),
+      vec![(DiffKind::Removed, "purchased", "\"Sword\"")]
+    );
+  }
+}
@@ -0,0 +1,208 @@
+use std::{fmt, io, path::PathBuf, time::Duration};
+use subxt::error::{DispatchError, RpcError};
+
+use crate::ValidationProblem;
+
+/// Error of the library
+#[derive(Debug)]
+pub enum ImpexError {
+  /// Connection to the node failed or the node returned an error
+  Connection(subxt::Error),
+  /// The submitted call failed in the runtime
+  Dispatch {
+    /// Name of the pallet, e.g. `OrganizationIdentity`
+    pallet: String,
+    /// Name of the pallet error, e.g. `OrganizationExists`
+    error: String,
+    /// Documentation of the pallet error
+    description: String,
+  },
+  /// Data cannot be decoded or encoded
+  Decode(String),
+  /// The game spec cannot be used, e.g. it's written in an unknown format version
+  Spec(String),
+  /// Problems found by the validation of the game spec
+  Validation(Vec<ValidationProblem>),
+  /// The file cannot be read or written
+  File { path: PathBuf, source: io::Error },
+  /// The item is not found in the network or in the game spec
+  NotFound(String),
+  /// Invalid argument, e.g. a seed, an address or a block
+  InvalidArgument(String),
+  /// The import failed midway, its progress is saved to the journal
+  Interrupted {
+    journal: PathBuf,
+    source: Box<ImpexError>,
+  },
+  /// The transaction was not accepted by the transaction pool or was removed from it
+  Rejected(Rejection),
+  /// The transaction was not included in a block within the given time
+  Timeout(Duration),
+  /// The transaction failed to confirm, but its nonce is used, so it may be included in a block.
+  ///
+  /// Its result is unknown and it's not submitted again, as its calls would be applied twice.
+  Unconfirmed(Box<ImpexError>),
+}
+
+/// Reason why the transaction pool did not include the transaction
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Rejection {
+  /// The nonce of the transaction is already used
+  Stale,
+  /// The nonce of the transaction is ahead of the next nonce of the account
+  Future,
+  /// Another transaction with the same nonce is in the pool and has a higher priority
+  PriorityTooLow,
+  /// The transaction was dropped from the pool, e.g. because the pool is full, or was replaced
+  /// by another transaction with the same nonce
+  Dropped,
+  /// The transaction cannot be included, e.g. the account cannot pay the fees
+  Invalid(String),
+}
+
+impl fmt::Display for Rejection {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Rejection::Stale => f.write_str("Transaction is outdated"),
+      Rejection::Future => f.write_str("Transaction will be valid in the future"),
+      Rejection::PriorityTooLow => f.write_str("Priority is too low"),
+      Rejection::Dropped => f.write_str("Transaction was dropped from the pool"),
+      Rejection::Invalid(message) => write!(f, "Invalid transaction: {}", message),
+    }
+  }
+}
+
+impl ImpexError {
+  /// Error of the file operation with the given path
+  pub(crate) fn file<P: Into<PathBuf>>(path: P, source: io::Error) -> ImpexError {
+    ImpexError::File {
+      path: path.into(),
+      source,
+    }
+  }
+
+  /// Returns true if the failure is transient and the transaction may succeed if it's submitted
+  /// again, e.g. the connection dropped or the transaction pool rejected the transaction.
+  ///
+  /// Calls failed in the runtime are never retried, they fail the same way again.
+  pub fn is_retryable(&self) -> bool {
+    match self {
+      ImpexError::Connection(subxt::Error::Rpc(_) | subxt::Error::Io(_)) => true,
+      ImpexError::Rejected(rejection) => !matches!(rejection, Rejection::Invalid(_)),
+      ImpexError::Timeout(_) => true,
+      _ => false,
+    }
+  }
+}
+
+impl fmt::Display for ImpexError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ImpexError::Connection(e) => write!(f, "{}", e),
+      ImpexError::Dispatch {
+        pallet,
+        error,
+        description,
+      } => {
+        write!(f, "{}.{}", pallet, error)?;
+        if !description.is_empty() {
+          write!(f, ": {}", description)?;
+        }
+        Ok(())
+      },
+      ImpexError::Decode(message)
+      | ImpexError::Spec(message)
+      | ImpexError::NotFound(message)
+      | ImpexError::InvalidArgument(message) => f.write_str(message),
+      ImpexError::Validation(problems) => {
+        write!(f, "Game spec has {} problem(s)", problems.len())
+      },
+      ImpexError::File { path, source } => write!(f, "{}: {}", path.display(), source),
+      ImpexError::Interrupted { journal, source } => {
+        write!(f, "{} (progress is saved to {})", source, journal.display())
+      },
+      ImpexError::Rejected(rejection) => write!(f, "{}", rejection),
+      ImpexError::Timeout(timeout) => write!(
+        f,
+        "Transaction was not included in a block within {} s",
+        timeout.as_secs_f32()
+      ),
+      ImpexError::Unconfirmed(source) => write!(
+        f,
+        "{}, but its nonce is used, so it may have been included in a block",
+        source
+      ),
+    }
+  }
+}
+
+impl std::error::Error for ImpexError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      ImpexError::Connection(e) => Some(e),
+      ImpexError::File { source, .. } => Some(source),
+      ImpexError::Interrupted { source, .. } | ImpexError::Unconfirmed(source) => {
+        Some(source.as_ref())
+      },
+      _ => None,
+    }
+  }
+}
+
+impl From<subxt::Error> for ImpexError {
+  fn from(e: subxt::Error) -> Self {
+    match e {
+      subxt::Error::Runtime(DispatchError::Module(e)) => ImpexError::Dispatch {
+        pallet: e.pallet,
+        error: e.error,
+        description: e.description.join(" ").trim().to_string(),
+      },
+      subxt::Error::Codec(e) => ImpexError::Decode(e.to_string()),
+      subxt::Error::Rpc(RpcError::ClientError(e)) => match pool_rejection(&e.to_string()) {
+        Some(rejection) => ImpexError::Rejected(rejection),
+        None => ImpexError::Connection(subxt::Error::Rpc(RpcError::ClientError(e))),
+      },
+      e => ImpexError::Connection(e),
+    }
+  }
+}
+
+/// Rejection of the transaction by the pool, recognized by the message of the RPC error
+fn pool_rejection(message: &str) -> Option<Rejection> {
+  if message.contains("Transaction is outdated") {
+    Some(Rejection::Stale)
+  } else if message.contains("Transaction will be valid in the future") {
+    Some(Rejection::Future)
+  } else if message.contains("Priority is too low") {
+    Some(Rejection::PriorityTooLow)
+  } else if message.contains("Invalid Transaction")
+    || message.contains("Transaction is temporarily banned")
+  {
+    Some(Rejection::Invalid(message.to_string()))
+  } else {
+    None
+  }
+}
+
+/// Errors of the libraries which decode or encode the data
+macro_rules! decode_error {
+  ($($error:ty),*) => {
+    $(
+      impl From<$error> for ImpexError {
+        fn from(e: $error) -> Self {
+          ImpexError::Decode(e.to_string())
+        }
+      }
+    )*
+  };
+}
+
+decode_error!(
+  parity_scale_codec::Error,
+  serde_json::Error,
+  serde_yaml::Error,
+  toml::de::Error,
+  toml::ser::Error,
+  subxt::error::MetadataError,
+  subxt::metadata::InvalidMetadataError
+);
@@ -0,0 +1,274 @@
+use serde::{de::DeserializeOwned, Serialize};
+use std::{fmt, path::Path, str::FromStr};
+
+use crate::{ImpexError, ResultOf};
+
+/// Format of the game spec file
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum SpecFormat {
+  /// Compact JSON
+  #[default]
+  Json,
+  /// Indented JSON
+  JsonPretty,
+  Yaml,
+  Toml,
+}
+
+impl SpecFormat {
+  /// Detects the format by the extension of the file, JSON is used for unknown extensions
+  pub fn from_path<P: AsRef<Path>>(path: P) -> SpecFormat {
+    let extension = path
+      .as_ref()
+      .extension()
+      .and_then(|ext| ext.to_str())
+      .map(|ext| ext.to_ascii_lowercase());
+    match extension.as_deref() {
+      Some("yaml") | Some("yml") => SpecFormat::Yaml,
+      Some("toml") => SpecFormat::Toml,
+      _ => SpecFormat::Json,
+    }
+  }
+
+  /// Parses the content written in the format
+  pub(crate) fn parse<T: DeserializeOwned>(&self, content: &str) -> ResultOf<T> {
+    Ok(match self {
+      SpecFormat::Json | SpecFormat::JsonPretty => serde_json::from_str(content)?,
+      SpecFormat::Yaml => serde_yaml::from_str(content)?,
+      // the TOML deserializer does not support `u128` balances, so the value is read
+      // through the JSON tree
+      SpecFormat::Toml => serde_json::from_value(json_from_toml(toml::from_str(content)?))?,
+    })
+  }
+
+  /// Parses the content written in the format into the JSON tree.
+  ///
+  /// Enum variants tagged in YAML become single key objects like in JSON.
+  pub(crate) fn parse_tree(&self, content: &str) -> ResultOf<serde_json::Value> {
+    Ok(match self {
+      SpecFormat::Json | SpecFormat::JsonPretty => serde_json::from_str(content)?,
+      SpecFormat::Yaml => json_from_yaml(serde_yaml::from_str(content)?)?,
+      SpecFormat::Toml => json_from_toml(toml::from_str(content)?),
+    })
+  }
+
+  /// Writes the value in the format
+  pub(crate) fn write<T: Serialize + DeserializeOwned>(&self, value: &T) -> ResultOf<String> {
+    Ok(match self {
+      SpecFormat::Json => serde_json::to_string(value)?,
+      SpecFormat::JsonPretty => serde_json::to_string_pretty(value)? + "\n",
+      SpecFormat::Yaml => serde_yaml::to_string(value)?,
+      SpecFormat::Toml => {
+        // the TOML serializer supports neither `u128` balances nor `null`,
+        // so the value is written through the YAML tree
+        let content = toml::to_string(&toml_from_yaml(serde_yaml::to_value(value)?)?)?;
+        // balances which are beyond the range of TOML integers are written as strings
+        // and cannot be read back
+        self
+          .parse::<T>(&content)
+          .map_err(|e| ImpexError::Spec(format!("Game spec cannot be written as TOML: {}", e)))?;
+        content
+      },
+    })
+  }
+}
+
+/// Converts the YAML tree to the TOML one.
+///
+/// `null` values are omitted, enum variants tagged in YAML become single key tables like in JSON.
+fn toml_from_yaml(value: serde_yaml::Value) -> ResultOf<Option<toml::Value>> {
+  use serde_yaml::Value;
+  Ok(match value {
+    Value::Null => None,
+    Value::Bool(b) => Some(toml::Value::Boolean(b)),
+    Value::Number(n) => match (n.as_i64(), n.as_f64()) {
+      (Some(i), _) => Some(toml::Value::Integer(i)),
+      (None, _) if n.is_u64() => {
+        return Err(ImpexError::Spec(format!(
+          "Number {} is beyond the range of TOML integers",
+          n
+        )))
+      },
+      (None, Some(f)) => Some(toml::Value::Float(f)),
+      (None, None) => return Err(ImpexError::Decode(format!("Unsupported number {}", n))),
+    },
+    Value::String(s) => Some(toml::Value::String(s)),
+    Value::Sequence(items) => {
+      let mut array = vec![];
+      for item in items {
+        array.push(
+          toml_from_yaml(item)?
+            .ok_or_else(|| ImpexError::Spec("TOML does not support null in arrays".to_string()))?,
+        );
+      }
+      Some(toml::Value::Array(array))
+    },
+    Value::Mapping(mapping) => {
+      let mut table = toml::Table::new();
+      for (key, value) in mapping {
+        let key = match key {
+          Value::String(key) => key,
+          key => serde_yaml::to_string(&key)?.trim_end().to_string(),
+        };
+        if let Some(value) = toml_from_yaml(value)? {
+          table.insert(key, value);
+        }
+      }
+      Some(toml::Value::Table(table))
+    },
+    Value::Tagged(tagged) => {
+      let variant = tagged.tag.to_string().trim_start_matches('!').to_string();
+      let mut table = toml::Table::new();
+      if let Some(value) = toml_from_yaml(tagged.value)? {
+        table.insert(variant, value);
+      }
+      Some(toml::Value::Table(table))
+    },
+  })
+}
+
+/// Converts the YAML tree to the JSON one, enum variants tagged in YAML become single key objects
+fn json_from_yaml(value: serde_yaml::Value) -> ResultOf<serde_json::Value> {
+  use serde_json::Value;
+  Ok(match value {
+    serde_yaml::Value::Tagged(tagged) => {
+      let variant = tagged.tag.to_string().trim_start_matches('!').to_string();
+      let mut object = serde_json::Map::new();
+      object.insert(variant, json_from_yaml(tagged.value)?);
+      Value::Object(object)
+    },
+    serde_yaml::Value::Sequence(items) => Value::Array(
+      items
+        .into_iter()
+        .map(json_from_yaml)
+        .collect::<ResultOf<_>>()?,
+    ),
+    serde_yaml::Value::Mapping(mapping) => {
+      let mut object = serde_json::Map::new();
+      for (key, value) in mapping {
+        let key = match key {
+          serde_yaml::Value::String(key) => key,
+          key => serde_yaml::to_string(&key)?.trim_end().to_string(),
+        };
+        object.insert(key, json_from_yaml(value)?);
+      }
+      Value::Object(object)
+    },
+    value => serde_json::to_value(value)?,
+  })
+}
+
+/// Converts the TOML tree to the JSON one
+fn json_from_toml(value: toml::Value) -> serde_json::Value {
+  use serde_json::Value;
+  match value {
+    toml::Value::String(s) => Value::String(s),
+    toml::Value::Integer(i) => Value::from(i),
+    toml::Value::Float(f) => Value::from(f),
+    toml::Value::Boolean(b) => Value::Bool(b),
+    toml::Value::Datetime(d) => Value::String(d.to_string()),
+    toml::Value::Array(items) => Value::Array(items.into_iter().map(json_from_toml).collect()),
+    toml::Value::Table(table) => Value::Object(
+      table
+        .into_iter()
+        .map(|(key, value)| (key, json_from_toml(value)))
+        .collect(),
+    ),
+  }
+}
+
+impl FromStr for SpecFormat {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "json" => Ok(SpecFormat::Json),
+      "json-pretty" => Ok(SpecFormat::JsonPretty),
+      "yaml" => Ok(SpecFormat::Yaml),
+      "toml" => Ok(SpecFormat::Toml),
+      _ => Err(format!(
+        "Unknown format {}, expected one of: json, json-pretty, yaml, toml",
+        s
+      )),
+    }
+  }
+}
+
+impl fmt::Display for SpecFormat {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let name = match self {
+      SpecFormat::Json => "json",
+      SpecFormat::JsonPretty => "json-pretty",
+      SpecFormat::Yaml => "yaml",
+      SpecFormat::Toml => "toml",
+    };
+    f.write_str(name)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use super::*;
+  use crate::{
+    game_spec::tests::game_spec,
+    migration::{read_game_spec, write_game_spec},
+  };
+
+  const USER: &str = "5FLSigC9HGRKVhB9FiEo4Y3koPsNmBmLJbpXg2mp1hXcS59Y";
+
+  #[test]
+  fn format_is_detected_by_extension() {
+    assert_eq!(SpecFormat::from_path("spec.yaml"), SpecFormat::Yaml);
+    assert_eq!(SpecFormat::from_path("spec.YML"), SpecFormat::Yaml);
+    assert_eq!(SpecFormat::from_path("spec.toml"), SpecFormat::Toml);
+    assert_eq!(SpecFormat::from_path("spec.json"), SpecFormat::Json);
+    assert_eq!(SpecFormat::from_path("spec"), SpecFormat::Json);
+  }
+
+  #[test]
+  fn spec_round_trips_in_every_format() {
+    let spec = game_spec(|content| {
+      content["balances"] = json!([[USER, "gold", 5], [USER, "energy", i64::MAX]]);
+    });
+    let expected = serde_json::to_string(&spec).unwrap();
+    for format in [
+      SpecFormat::Json,
+      SpecFormat::JsonPretty,
+      SpecFormat::Yaml,
+      SpecFormat::Toml,
+    ] {
+      let content = write_game_spec(&spec, format).unwrap();
+      let (read, _) = read_game_spec(&content, format).unwrap();
+      assert_eq!(
+        serde_json::to_string(&read).unwrap(),
+        expected,
+        "{}",
+        format
+      );
+    }
+  }
+
+  #[test]
+  fn yaml_writes_variants_as_tags() {
+    let spec = game_spec(|_| {});
+    let content = write_game_spec(&spec, SpecFormat::Yaml).unwrap();
+    assert!(content.contains("- !Fa\n"), "{}", content);
+  }
+
+  #[test]
+  fn toml_rejects_balances_beyond_i64() {
+    let spec = game_spec(|content| {
+      content["balances"] = json!([[USER, "gold", u64::MAX]]);
+    });
+    let result = SpecFormat::Toml.write(&spec);
+    assert!(matches!(
+      result,
+      Err(ImpexError::Spec(message)) if message.contains("beyond the range of TOML integers")
+    ));
+    // the other formats keep the balance
+    let content = SpecFormat::Json.write(&spec).unwrap();
+    assert!(content.contains(&u64::MAX.to_string()));
+  }
+}
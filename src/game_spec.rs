@@ -1,17 +1,37 @@
 use serde::{Deserialize, Serialize};
 
 use crate::{
-  AttributesDetails, FungibleAssetIds, NonFungibleClassDetails, NonFungibleClassId,
-  NonFungibleDetails, OrganizationDetails,
+  finalbiome::runtime_types::pallet_support::AttributeValue, handle::AssetIds,
+  migration::SPEC_FORMAT_VERSION, AttributeKey, AttributesDetails, FungibleAssetBalances,
+  FungibleAssetIds, ImpexError, NonFungibleAssetId, NonFungibleClassDetails, NonFungibleClassId,
+  NonFungibleDetails, OrganizationDetails, ResultOf,
 };
 
-type OrganizationMembers = Vec<sp_runtime::AccountId32>;
+pub(crate) type OrganizationMembers = Vec<sp_runtime::AccountId32>;
+pub(crate) type NonFungibleInstances = Vec<NonFungibleInstance>;
+
+/// Minted instance of the NFA class held by an account
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct NonFungibleInstance {
+  /// Class of the instance
+  pub class: NonFungibleClassId,
+  /// Id of the instance in the network it was exported from
+  pub asset: NonFungibleAssetId,
+  /// Owner of the instance
+  pub owner: sp_runtime::AccountId32,
+  /// Whether the instance is locked by a mechanic
+  pub locked: bool,
+  /// Attributes of the instance
+  pub attributes: Vec<(AttributeKey, AttributeValue)>,
+}
 
 #[derive(Serialize, Deserialize, Clone)]
 /// A FinalBiome game spec struct which holds configuration of the game.
 pub struct GameSpec {
+  /// Version of the game spec format
+  pub spec_format_version: u32,
   /// Version of the node
-  pub version: String,
+  pub node_version: String,
   /// Hash of the network block when the game spec was exported
   pub hash: String,
   /// Game details
@@ -24,24 +44,36 @@ pub struct GameSpec {
   pub nfa: NonFungibleClassDetails,
   /// NFA Attributes
   pub attributes: AttributesDetails,
+  /// Users (players) of the organization, exported only on request
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub users: Option<OrganizationMembers>,
+  /// FA balances of the users, exported only on request
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub balances: Option<FungibleAssetBalances>,
+  /// Minted NFA instances, exported only on request
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub instances: Option<NonFungibleInstances>,
+  /// Ids of the assets by their handles, written to the spec file so it's read back with the
+  /// same ids. It's never read into the spec, the handles are resolved to the ids instead.
+  #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+  pub(crate) asset_ids: Option<AssetIds>,
 }
 
 impl GameSpec {
-  /// Returns nfa by given id
-  pub fn get_nfa(&self, id: NonFungibleClassId) -> NonFungibleDetails {
+  /// Returns nfa by given id, or `None` if the spec has no class with the id
+  pub fn get_nfa(&self, id: NonFungibleClassId) -> Option<NonFungibleDetails> {
     self
       .nfa
       .iter()
       .find(|(class_id, ..)| class_id == &id)
       .map(|(_class_id, details)| details.clone())
-      .unwrap_or_else(|| panic!("cannot find class with given id: {:?}", id))
   }
 }
 
 #[derive(Default)]
 pub(crate) struct GameSpecBuilder {
   /// Version of the node
-  pub version: String,
+  pub node_version: String,
   /// Hash of the network block when the game spec was exported
   pub hash: String,
   /// Game details
@@ -62,8 +94,8 @@ impl GameSpecBuilder {
   }
 
   /// Set version of the node
-  pub fn version(mut self, version: String) -> GameSpecBuilder {
-    self.version = version;
+  pub fn node_version(mut self, node_version: String) -> GameSpecBuilder {
+    self.node_version = node_version;
     self
   }
   /// Set hash of the state
@@ -108,19 +140,44 @@ impl GameSpecBuilder {
     self
   }
 
-  pub fn try_build(self) -> Result<GameSpec, Box<dyn std::error::Error>> {
-    if self.organization_details.is_none() {
-      return Err("Organization details not set".into());
-    };
+  pub fn try_build(self) -> ResultOf<GameSpec> {
+    let not_set = |item: &str| ImpexError::Spec(format!("{} not set", item));
 
     Ok(GameSpec {
-      version: self.version,
+      spec_format_version: SPEC_FORMAT_VERSION,
+      node_version: self.node_version,
       hash: self.hash,
-      organization_details: self.organization_details.expect("org details exists"),
-      organization_members: self.organization_members.expect("org members exists"),
-      fa: self.fa.expect("fa exists"),
-      nfa: self.nfa.expect("nfa exists"),
-      attributes: self.attributes.expect("nfa attrs exists"),
+      organization_details: self
+        .organization_details
+        .ok_or_else(|| not_set("Organization details"))?,
+      organization_members: self
+        .organization_members
+        .ok_or_else(|| not_set("Organization members"))?,
+      fa: self.fa.ok_or_else(|| not_set("FA"))?,
+      nfa: self.nfa.ok_or_else(|| not_set("NFA"))?,
+      attributes: self.attributes.ok_or_else(|| not_set("NFA attributes"))?,
+      users: None,
+      balances: None,
+      instances: None,
+      asset_ids: None,
     })
   }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+  use serde_json::Value;
+
+  use super::*;
+  use crate::{format::SpecFormat, migration::read_game_spec};
+
+  /// Game spec of the test fixture, edited by `edit` before it's read
+  pub(crate) fn game_spec(edit: impl FnOnce(&mut Value)) -> GameSpec {
+    let mut content: Value =
+      serde_json::from_str(include_str!("../tests/fixtures/game_spec.json")).unwrap();
+    edit(&mut content);
+    read_game_spec(&content.to_string(), SpecFormat::Json)
+      .unwrap()
+      .0
+  }
+}
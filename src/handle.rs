@@ -0,0 +1,342 @@
+use parity_scale_codec::{CompactAs, Decode, Encode};
+use serde::{
+  de::{self, IgnoredAny, Visitor},
+  Deserialize, Deserializer, Serialize, Serializer,
+};
+use serde_json::Value;
+use std::{
+  cell::RefCell,
+  collections::{BTreeMap, HashMap, HashSet},
+  fmt,
+};
+
+use crate::{
+  format::SpecFormat, game_spec::GameSpec, validate::ValidationProblem, ImpexError, ResultOf,
+};
+
+thread_local! {
+  /// Handles of the game spec which is being read or written
+  static HANDLES: RefCell<Option<Handles>> = const { RefCell::new(None) };
+}
+
+/// Symbolic handles of the assets of the game spec, e.g. `gold` or `sword`.
+///
+/// Handles are used instead of the numeric ids while the spec file is read or written, so
+/// assets can be declared and referenced without knowing the ids of any network.
+#[derive(Default, Clone, Debug)]
+pub(crate) struct Handles {
+  fa: HandleMap,
+  nfa: HandleMap,
+}
+
+/// Handles of the assets of one kind
+#[derive(Default, Clone, Debug)]
+struct HandleMap {
+  ids: HashMap<String, u32>,
+  handles: HashMap<u32, String>,
+}
+
+impl HandleMap {
+  fn insert(&mut self, handle: String, id: u32) {
+    self.ids.insert(handle.clone(), id);
+    self.handles.insert(id, handle);
+  }
+
+  /// Assigns ids to the declared handles, numeric ids are kept as is.
+  ///
+  /// Handles get the ids recorded in the spec file, the others get free ids. Returns the map and
+  /// the problems of the declarations by the indices of the entries, the invalid declarations
+  /// are skipped.
+  fn assign(
+    keys: Vec<AssetKey>,
+    recorded: &BTreeMap<String, u32>,
+  ) -> (HandleMap, Vec<(usize, String)>) {
+    let mut map = HandleMap::default();
+    let mut problems = vec![];
+    let mut used: HashSet<u32> = keys
+      .iter()
+      .filter_map(|key| match key {
+        AssetKey::Id(id) => Some(*id),
+        _ => None,
+      })
+      .collect();
+    let mut next_id = used
+      .iter()
+      .chain(recorded.values())
+      .max()
+      .map_or(0, |id| id + 1);
+    for (idx, key) in keys.into_iter().enumerate() {
+      let AssetKey::Handle(handle) = key else {
+        continue;
+      };
+      if map.ids.contains_key(&handle) {
+        problems.push((idx, format!("Handle {} is declared more than once", handle)));
+        continue;
+      }
+      let id = match recorded.get(&handle) {
+        Some(id) => *id,
+        None => {
+          let id = next_id;
+          next_id += 1;
+          id
+        },
+      };
+      if !used.insert(id) {
+        problems.push((
+          idx,
+          format!("Id {} of handle {} is used by another asset", id, handle),
+        ));
+        continue;
+      }
+      map.insert(handle, id);
+    }
+    (map, problems)
+  }
+
+  /// Generates unique handles from the names of the assets
+  fn generate<'a>(assets: impl Iterator<Item = (u32, &'a [u8])>, fallback: &str) -> HandleMap {
+    let mut map = HandleMap::default();
+    for (id, name) in assets {
+      let base = handle_of(name, fallback);
+      let mut handle = base.clone();
+      let mut n = 2;
+      while map.ids.contains_key(&handle) {
+        handle = format!("{}_{}", base, n);
+        n += 1;
+      }
+      map.insert(handle, id);
+    }
+    map
+  }
+}
+
+/// Makes a handle from the asset name: lowercase alphanumeric words joined by `_`
+fn handle_of(name: &[u8], fallback: &str) -> String {
+  let mut handle = String::new();
+  for c in String::from_utf8_lossy(name).chars() {
+    if c.is_ascii_alphanumeric() {
+      handle.push(c.to_ascii_lowercase());
+    } else if !handle.is_empty() && !handle.ends_with('_') {
+      handle.push('_');
+    }
+  }
+  match handle.trim_end_matches('_') {
+    "" => fallback.to_string(),
+    handle => handle.to_string(),
+  }
+}
+
+/// Key of the asset entry in the spec file
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum AssetKey {
+  Id(u32),
+  Handle(String),
+  /// Neither an id nor a handle, it's reported when the entry itself is read
+  Invalid(IgnoredAny),
+}
+
+/// Ids of the assets by the handles which declare them in the spec file
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct AssetIds {
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  fa: BTreeMap<String, u32>,
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  nfa: BTreeMap<String, u32>,
+}
+
+/// Keys of the asset entries, which are read before the spec itself
+#[derive(Deserialize)]
+struct SpecKeys {
+  #[serde(default)]
+  fa: Vec<(AssetKey, IgnoredAny)>,
+  #[serde(default)]
+  nfa: Vec<(AssetKey, IgnoredAny)>,
+  #[serde(default)]
+  asset_ids: AssetIds,
+}
+
+impl Handles {
+  /// Collects the handles declared by the asset entries of the spec file
+  pub(crate) fn declared(content: &str, format: SpecFormat) -> ResultOf<Handles> {
+    let keys: SpecKeys = format.parse(content)?;
+    let (fa, fa_problems) = HandleMap::assign(
+      keys.fa.into_iter().map(|(key, _)| key).collect(),
+      &keys.asset_ids.fa,
+    );
+    let (nfa, nfa_problems) = HandleMap::assign(
+      keys.nfa.into_iter().map(|(key, _)| key).collect(),
+      &keys.asset_ids.nfa,
+    );
+    match fa_problems.into_iter().chain(nfa_problems).next() {
+      Some((_, message)) => Err(ImpexError::Spec(message)),
+      None => Ok(Handles { fa, nfa }),
+    }
+  }
+
+  /// Collects the handles declared by the asset entries of the spec tree, which may be invalid.
+  ///
+  /// Returns the handles of the valid declarations, the problems of the others and the indices
+  /// of the entries with the invalid declarations by the names of the lists.
+  pub(crate) fn declared_leniently(
+    tree: &Value,
+  ) -> (
+    Handles,
+    Vec<ValidationProblem>,
+    HashMap<&'static str, HashSet<usize>>,
+  ) {
+    let mut problems = vec![];
+    let mut invalid = HashMap::new();
+    let recorded: AssetIds = match tree.get("asset_ids") {
+      Some(ids) => serde_json::from_value(ids.clone()).unwrap_or_else(|e| {
+        problems.push(ValidationProblem::new("$.asset_ids".into(), e.to_string()));
+        AssetIds::default()
+      }),
+      None => AssetIds::default(),
+    };
+    let mut assign = |kind: &'static str, recorded: &BTreeMap<String, u32>| {
+      let keys = tree
+        .get(kind)
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .map(|entry| {
+          let key = entry.get(0).cloned().unwrap_or_default();
+          serde_json::from_value(key).unwrap_or(AssetKey::Invalid(IgnoredAny))
+        })
+        .collect();
+      let (map, map_problems) = HandleMap::assign(keys, recorded);
+      for (idx, message) in map_problems {
+        problems.push(ValidationProblem::new(
+          format!("$.{}[{}][0]", kind, idx),
+          message,
+        ));
+        invalid.entry(kind).or_insert_with(HashSet::new).insert(idx);
+      }
+      map
+    };
+    let fa = assign("fa", &recorded.fa);
+    let nfa = assign("nfa", &recorded.nfa);
+    (Handles { fa, nfa }, problems, invalid)
+  }
+
+  /// Returns the ids of the assets by their handles, to be recorded in the spec file
+  pub(crate) fn asset_ids(&self) -> AssetIds {
+    let ids = |map: &HandleMap| map.ids.iter().map(|(h, id)| (h.clone(), *id)).collect();
+    AssetIds {
+      fa: ids(&self.fa),
+      nfa: ids(&self.nfa),
+    }
+  }
+
+  /// Generates the handles of all assets of the spec from their names
+  pub(crate) fn generate(game_spec: &GameSpec) -> Handles {
+    Handles {
+      fa: HandleMap::generate(
+        game_spec
+          .fa
+          .iter()
+          .map(|(id, details)| (id.0, &details.name.0[..])),
+        "fa",
+      ),
+      nfa: HandleMap::generate(
+        game_spec
+          .nfa
+          .iter()
+          .map(|(id, details)| (id.0, &details.name.0[..])),
+        "nfa",
+      ),
+    }
+  }
+
+  /// Runs `f` with the handles used for serialization and deserialization of asset ids
+  pub(crate) fn scope<R>(self, f: impl FnOnce() -> R) -> R {
+    /// Restores the previous handles, even if `f` panics
+    struct Guard(Option<Handles>);
+    impl Drop for Guard {
+      fn drop(&mut self) {
+        HANDLES.with(|handles| *handles.borrow_mut() = self.0.take());
+      }
+    }
+    let _guard = Guard(HANDLES.with(|handles| handles.borrow_mut().replace(self)));
+    f()
+  }
+}
+
+/// Declares an asset id which is written as a handle if the handles are in scope
+macro_rules! asset_id {
+  ($(#[$attr:meta])* $name:ident, $kind:ident, $label:literal) => {
+    $(#[$attr])*
+    #[derive(Encode, Decode, CompactAs, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+    pub struct $name(pub u32);
+
+    impl Serialize for $name {
+      fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let handle = HANDLES.with(|handles| {
+          handles
+            .borrow()
+            .as_ref()
+            .and_then(|handles| handles.$kind.handles.get(&self.0).cloned())
+        });
+        match handle {
+          Some(handle) => serializer.serialize_str(&handle),
+          None => serializer.serialize_u32(self.0),
+        }
+      }
+    }
+
+    impl<'de> Deserialize<'de> for $name {
+      fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct IdVisitor;
+
+        impl<'de> Visitor<'de> for IdVisitor {
+          type Value = $name;
+
+          fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str(concat!("an id or a handle of ", $label))
+          }
+
+          fn visit_u64<E: de::Error>(self, id: u64) -> Result<Self::Value, E> {
+            u32::try_from(id)
+              .map($name)
+              .map_err(|_| E::invalid_value(de::Unexpected::Unsigned(id), &self))
+          }
+
+          fn visit_i64<E: de::Error>(self, id: i64) -> Result<Self::Value, E> {
+            u32::try_from(id)
+              .map($name)
+              .map_err(|_| E::invalid_value(de::Unexpected::Signed(id), &self))
+          }
+
+          fn visit_str<E: de::Error>(self, handle: &str) -> Result<Self::Value, E> {
+            HANDLES
+              .with(|handles| {
+                handles
+                  .borrow()
+                  .as_ref()
+                  .and_then(|handles| handles.$kind.ids.get(handle).copied())
+              })
+              .map($name)
+              .ok_or_else(|| E::custom(format!(concat!("unknown ", $label, " handle {}"), handle)))
+          }
+        }
+
+        deserializer.deserialize_any(IdVisitor)
+      }
+    }
+  };
+}
+
+asset_id!(
+  /// Id of the fungible asset, substitute of the runtime type with the same SCALE encoding
+  FungibleAssetId,
+  fa,
+  "FA"
+);
+
+asset_id!(
+  /// Id of the non fungible class, substitute of the runtime type with the same SCALE encoding
+  NonFungibleClassId,
+  nfa,
+  "NFA"
+);
@@ -0,0 +1,125 @@
+use serde::Serialize;
+use sp_runtime::AccountId32;
+use std::fmt;
+
+use crate::{FungibleAssetBalance, FungibleAssetId, NonFungibleAssetId, NonFungibleClassId};
+
+/// Running state of the game at a block: mechanics in flight and pending top-ups
+#[derive(Serialize, Clone, Debug)]
+pub struct GameInspection {
+  /// Game address
+  pub organization: AccountId32,
+  /// Hash of the inspected block
+  pub hash: String,
+  /// Number of the inspected block
+  pub block_number: u64,
+  /// Mechanics which are started by the users of the game and not finished yet
+  pub mechanics: Vec<MechanicState>,
+  /// FA of the game which are replenished by top-ups
+  pub top_upped_fa: Vec<FungibleAssetId>,
+  /// Accounts waiting for the top-up of the FA
+  pub top_up_queue: Vec<TopUpState>,
+}
+
+/// Mechanic which is in progress
+#[derive(Serialize, Clone, Debug)]
+pub struct MechanicState {
+  /// User who started the mechanic
+  pub gamer: AccountId32,
+  /// Id of the mechanic among the mechanics of the user
+  pub id: u32,
+  /// Block at which the mechanic is dropped by timeout
+  pub timeout: u32,
+  pub data: MechanicKind,
+  /// Assets locked by the mechanic
+  pub locked: Vec<LockedAsset>,
+}
+
+/// Kind of the mechanic with its intermediate data
+#[derive(Serialize, Clone, Debug)]
+pub enum MechanicKind {
+  BuyNfa,
+  /// Bet with the outcomes of the played rounds
+  Bet {
+    outcomes: Vec<u32>,
+  },
+}
+
+/// Asset locked by the mechanic
+#[derive(Serialize, Clone, Debug)]
+pub enum LockedAsset {
+  Fa(FungibleAssetId, FungibleAssetBalance),
+  Nfa(NonFungibleClassId, NonFungibleAssetId),
+}
+
+/// Account waiting for the top-up of the FA
+#[derive(Serialize, Clone, Debug)]
+pub struct TopUpState {
+  pub fa: FungibleAssetId,
+  pub account: AccountId32,
+  /// Current balance of the account
+  pub balance: FungibleAssetBalance,
+}
+
+impl GameInspection {
+  /// Returns true if the mechanic should have been dropped by timeout already
+  pub fn is_expired(&self, mechanic: &MechanicState) -> bool {
+    u64::from(mechanic.timeout) <= self.block_number
+  }
+}
+
+impl fmt::Display for GameInspection {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(
+      f,
+      "Game {} at block {} ({})",
+      self.organization, self.block_number, self.hash
+    )?;
+
+    writeln!(f, "\nMechanics: {}", self.mechanics.len())?;
+    for mechanic in &self.mechanics {
+      write!(f, "  {} #{} ", mechanic.gamer, mechanic.id)?;
+      match &mechanic.data {
+        MechanicKind::BuyNfa => write!(f, "buy NFA")?,
+        MechanicKind::Bet { outcomes } => write!(
+          f,
+          "bet, {} round(s) played, outcomes {:?}",
+          outcomes.len(),
+          outcomes
+        )?,
+      }
+      let locked: Vec<String> = mechanic
+        .locked
+        .iter()
+        .map(|asset| match asset {
+          LockedAsset::Fa(fa, amount) => format!("FA {} x{}", fa.0, amount.0),
+          LockedAsset::Nfa(class, asset) => format!("NFA {}/{}", class.0, asset.0),
+        })
+        .collect();
+      if !locked.is_empty() {
+        write!(f, ", locked: {}", locked.join(", "))?;
+      }
+      write!(f, ", timeout at block {}", mechanic.timeout)?;
+      if self.is_expired(mechanic) {
+        write!(f, " (expired)")?;
+      }
+      writeln!(f)?;
+    }
+
+    let top_upped: Vec<String> = self
+      .top_upped_fa
+      .iter()
+      .map(|fa| fa.0.to_string())
+      .collect();
+    writeln!(f, "\nTop-upped FA: {}", top_upped.join(", "))?;
+    writeln!(f, "Top-up queue: {}", self.top_up_queue.len())?;
+    for top_up in &self.top_up_queue {
+      writeln!(
+        f,
+        "  FA {} <- {}, balance {}",
+        top_up.fa.0, top_up.account, top_up.balance.0
+      )?;
+    }
+    Ok(())
+  }
+}
@@ -0,0 +1,201 @@
+use serde::{Deserialize, Serialize};
+use sp_core::hexdisplay::HexDisplay;
+use std::{
+  collections::HashMap,
+  fs::{self, File},
+  io::BufReader,
+  path::Path,
+};
+
+use crate::{
+  plan::ImportPlan, report::StepReport, ExistingGame, FungibleAssetId, ImpexError,
+  NonFungibleClassId, ResultOf,
+};
+
+/// Progress of the import which is stored on disk after each submitted step.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct ImportJournal {
+  /// Hash of the import plan the journal belongs to
+  pub plan_hash: String,
+  /// Number of completed steps of the import plan
+  pub completed_steps: usize,
+  /// Original and created ids of the FA
+  pub fa_ids_map: Vec<(FungibleAssetId, FungibleAssetId)>,
+  /// Original and created ids of the NFA
+  pub nfa_ids_map: Vec<(NonFungibleClassId, NonFungibleClassId)>,
+  /// Results of the completed steps
+  pub steps: Vec<StepReport>,
+  /// Steps completed after the first failed step, they are not submitted again on resume.
+  ///
+  /// Calls of the same signer are submitted without waiting for each other, so the calls which
+  /// follow the failed one may still be included in the blocks.
+  #[serde(default)]
+  pub completed_ahead: Vec<usize>,
+  /// Steps submitted with the results unknown, e.g. they timed out but their nonces are used.
+  ///
+  /// They may be included in the blocks, so the import is not resumed over them.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub unconfirmed: Vec<usize>,
+  /// State of the existing game the plan was built from, so the same plan is built on resume
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub(crate) existing: Option<ExistingGame>,
+}
+
+impl ImportJournal {
+  /// Creates an empty journal for the plan
+  pub fn new(plan: &ImportPlan) -> ResultOf<ImportJournal> {
+    Ok(ImportJournal {
+      plan_hash: plan_hash(plan)?,
+      completed_steps: 0,
+      fa_ids_map: plan.fa_ids_map.clone(),
+      nfa_ids_map: plan.nfa_ids_map.clone(),
+      steps: vec![],
+      completed_ahead: vec![],
+      unconfirmed: vec![],
+      existing: None,
+    })
+  }
+
+  /// Loads the journal from file
+  pub fn load<P: AsRef<Path>>(path: P) -> ResultOf<ImportJournal> {
+    let f = File::open(&path).map_err(|e| ImpexError::file(path.as_ref(), e))?;
+    Ok(serde_json::from_reader(BufReader::new(f))?)
+  }
+
+  /// Checks that the journal loaded from the file was written for the given plan
+  pub fn check<P: AsRef<Path>>(&self, path: P, plan: &ImportPlan) -> ResultOf<()> {
+    if self.plan_hash != plan_hash(plan)? {
+      return Err(ImpexError::InvalidArgument(format!(
+        "Journal {} was written for another game spec or signers",
+        path.as_ref().display()
+      )));
+    }
+    if self.completed_steps > plan.steps.len()
+      || self
+        .completed_ahead
+        .iter()
+        .chain(&self.unconfirmed)
+        .any(|step| *step >= plan.steps.len())
+    {
+      return Err(ImpexError::Decode(format!(
+        "Journal {} is corrupted",
+        path.as_ref().display()
+      )));
+    }
+    if !self.unconfirmed.is_empty() {
+      let steps: Vec<_> = self
+        .unconfirmed
+        .iter()
+        .map(|step| step.to_string())
+        .collect();
+      return Err(ImpexError::InvalidArgument(format!(
+        "Results of the steps {} recorded in journal {} are unknown, so the import cannot be \
+         resumed. Remove the journal and run the import again, the items which exist in the \
+         network are not created again",
+        steps.join(", "),
+        path.as_ref().display()
+      )));
+    }
+    Ok(())
+  }
+
+  /// Writes the journal to file.
+  ///
+  /// The journal is written to a temporary file first, so it is never left half written.
+  pub fn save<P: AsRef<Path>>(&self, path: P) -> ResultOf<()> {
+    let tmp_path = path.as_ref().with_extension("tmp");
+    let f = File::create(&tmp_path).map_err(|e| ImpexError::file(&tmp_path, e))?;
+    serde_json::to_writer_pretty(f, self)?;
+    fs::rename(&tmp_path, &path).map_err(|e| ImpexError::file(path.as_ref(), e))?;
+    Ok(())
+  }
+
+  /// Returns the map of original and created ids of the FA
+  pub fn fa_ids_map(&self) -> HashMap<FungibleAssetId, FungibleAssetId> {
+    self.fa_ids_map.iter().cloned().collect()
+  }
+
+  /// Returns the map of original and created ids of the NFA
+  pub fn nfa_ids_map(&self) -> HashMap<NonFungibleClassId, NonFungibleClassId> {
+    self.nfa_ids_map.iter().cloned().collect()
+  }
+}
+
+/// Hash of the plan which ties the journal to the spec and signers
+fn plan_hash(plan: &ImportPlan) -> ResultOf<String> {
+  let encoded = serde_json::to_vec(plan)?;
+  let hash = sp_core::hashing::blake2_256(&encoded);
+  Ok(format!("0x{}", HexDisplay::from(&hash)))
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use super::*;
+  use crate::game_spec::tests::game_spec;
+
+  #[test]
+  fn plan_of_existing_game_is_built_again_on_resume() {
+    // the game exists with gold only, which has another id in the network
+    let live_spec = game_spec(|content| {
+      content["fa"] = json!([[9, content["fa"][0][1]]]);
+      content["nfa"] = json!([]);
+      content["attributes"] = json!([]);
+      content["organization_details"]["onboarding_assets"] = json!(null);
+    });
+    let game_spec = game_spec(|_| {});
+    let existing = ExistingGame {
+      spec: live_spec,
+      users: vec![],
+    };
+    let organization = game_spec.fa[0].1.owner.clone();
+    let manager = game_spec.organization_members[0].clone();
+    let plan = ImportPlan::complete(
+      &game_spec,
+      &existing.spec,
+      organization.clone(),
+      manager.clone(),
+    );
+    let mut journal = ImportJournal::new(&plan).unwrap();
+    journal.existing = Some(existing);
+    journal.completed_steps = 1;
+
+    let path = std::env::temp_dir().join(format!("impex-journal-{}.json", std::process::id()));
+    journal.save(&path).unwrap();
+    let loaded = ImportJournal::load(&path);
+    fs::remove_file(&path).unwrap();
+    let loaded = loaded.unwrap();
+
+    let existing = loaded.existing.as_ref().unwrap();
+    let resumed = ImportPlan::complete(
+      &game_spec,
+      &existing.spec,
+      organization.clone(),
+      manager.clone(),
+    );
+    loaded.check(&path, &resumed).unwrap();
+    assert_eq!(loaded.completed_steps, 1);
+    // the plan of a new organization differs
+    let plan = ImportPlan::new(&game_spec, organization, manager);
+    assert!(loaded.check(&path, &plan).is_err());
+  }
+
+  #[test]
+  fn import_is_not_resumed_over_unconfirmed_steps() {
+    let game_spec = game_spec(|_| {});
+    let organization = game_spec.fa[0].1.owner.clone();
+    let manager = game_spec.organization_members[0].clone();
+    let plan = ImportPlan::new(&game_spec, organization, manager);
+    let mut journal = ImportJournal::new(&plan).unwrap();
+    journal.check("journal.json", &plan).unwrap();
+    // the step may be included in a block, so it must not be submitted again
+    journal.unconfirmed = vec![2];
+    let result = journal.check("journal.json", &plan);
+    assert!(
+      matches!(result, Err(ImpexError::InvalidArgument(_))),
+      "{:?}",
+      result.err()
+    );
+  }
+}
@@ -1,29 +1,33 @@
-use game_spec::GameSpec;
 use parity_scale_codec::Decode;
+use serde::{Deserialize, Serialize};
 use sp_core::{
-  crypto::{ExposeSecret, SecretString, Ss58Codec},
+  crypto::{ExposeSecret, Pair, SecretString, Ss58Codec},
   hexdisplay::HexDisplay,
 };
+use sp_core::{sr25519, H256};
 use sp_runtime::{self, AccountId32, MultiAddress, MultiSigner};
 use std::{
   collections::HashMap,
-  fs::File,
-  io::BufReader,
+  fs::{self, File},
+  ops::Range,
   path::{Path, PathBuf},
+  sync::Mutex,
+  time::Duration,
 };
 use subxt::{
   storage::address::{StorageHasher, StorageMapKey},
-  tx::{PairSigner, Signer},
   OnlineClient, PolkadotConfig,
 };
 
-use finalbiome::runtime_types::{self, pallet_support::characteristics::bettor::BettorWinning};
+use finalbiome::runtime_types;
+use futures::{stream, stream::FuturesOrdered, try_join, Future, StreamExt, TryStreamExt};
 use indicatif::{ProgressBar, ProgressStyle};
 
 use crate::{
-  game_spec::GameSpecBuilder,
-  runtime_types::pallet_support::{characteristics::Characteristic, Attribute},
-  utils::{submit_default, AllKeyIter},
+  game_spec::{GameSpecBuilder, NonFungibleInstances},
+  migration::{read_game_spec, write_game_spec},
+  plan::{remap_characteristic, remap_onboarding_assets},
+  utils::{account_from_key, fetch_value, fetch_values, key_after, key_suffix, AllKeyIter},
 };
 
 #[subxt::subxt(
@@ -45,26 +49,14 @@ use crate::{
     type = "pallet_fungible_assets::types::CupFA",
     derive = "serde::Serialize, serde::Deserialize"
   ),
-  derive_for_type(
-    type = "sp_runtime::bounded::bounded_vec::BoundedVec",
-    derive = "serde::Serialize, serde::Deserialize"
-  ),
   derive_for_type(
     type = "pallet_organization_identity::types::AirDropAsset",
     derive = "serde::Serialize, serde::Deserialize"
   ),
-  derive_for_type(
-    type = "pallet_support::types::fungible_asset_id::FungibleAssetId",
-    derive = "serde::Serialize, serde::Deserialize, Debug, Hash, Copy"
-  ),
   derive_for_type(
     type = "pallet_support::types::fungible_asset_balance::FungibleAssetBalance",
     derive = "serde::Serialize, serde::Deserialize"
   ),
-  derive_for_type(
-    type = "pallet_support::types::non_fungible_class_id::NonFungibleClassId",
-    derive = "serde::Serialize, serde::Deserialize, Debug, Hash, Copy"
-  ),
   derive_for_type(
     type = "pallet_support::types_nfa::ClassDetails",
     derive = "serde::Serialize, serde::Deserialize"
@@ -97,6 +89,10 @@ use crate::{
     type = "pallet_support::characteristics::purchased::Offer",
     derive = "serde::Serialize, serde::Deserialize"
   ),
+  derive_for_type(
+    type = "pallet_support::characteristics::Characteristic",
+    derive = "serde::Serialize, serde::Deserialize"
+  ),
   derive_for_type(
     type = "pallet_support::Attribute",
     derive = "serde::Serialize, serde::Deserialize"
@@ -108,43 +104,195 @@ use crate::{
   derive_for_type(
     type = "pallet_support::NumberAttribute",
     derive = "serde::Serialize, serde::Deserialize"
+  ),
+  derive_for_type(
+    type = "pallet_support::types::non_fungible_asset_id::NonFungibleAssetId",
+    derive = "serde::Serialize, serde::Deserialize"
   )
 )]
-pub mod finalbiome {}
+pub mod finalbiome {
+  #[subxt(substitute_type = "sp_runtime::bounded::bounded_vec::BoundedVec")]
+  use crate::bounded::BoundedVec;
+  #[subxt(substitute_type = "pallet_support::types::fungible_asset_id::FungibleAssetId")]
+  use crate::handle::FungibleAssetId;
+  #[subxt(
+    substitute_type = "pallet_support::types::non_fungible_class_id::NonFungibleClassId"
+  )]
+  use crate::handle::NonFungibleClassId;
+}
 
+mod backend;
+mod bounded;
+mod diff;
+mod error;
+mod format;
 mod game_spec;
+mod handle;
+mod inspect;
+mod journal;
+mod migration;
+#[cfg(any(test, feature = "testing"))]
+mod mock;
+mod plan;
+mod reconcile;
+mod report;
+mod schema;
+mod teardown;
 mod utils;
-
-type ResultOf<T> = Result<T, Box<dyn std::error::Error>>;
+mod validate;
+
+pub use backend::{ChainBackend, RuntimeCall, RuntimeEvent, SubmittedCall};
+pub use bounded::BoundedVec;
+pub use diff::{DiffEntry, DiffKind, SpecDiff};
+pub use error::{ImpexError, Rejection};
+pub use format::SpecFormat;
+pub use game_spec::{GameSpec, NonFungibleInstance};
+pub use inspect::{GameInspection, LockedAsset, MechanicKind, MechanicState, TopUpState};
+pub use journal::ImportJournal;
+pub use migration::SPEC_FORMAT_VERSION;
+#[cfg(any(test, feature = "testing"))]
+pub use mock::MemoryChain;
+pub use plan::{ImportCall, ImportPlan, ImportStep, StepSigner};
+pub use report::{EventReport, ImportReport, StepReport};
+pub use schema::game_spec_schema;
+pub use validate::ValidationProblem;
+
+pub type ResultOf<T> = Result<T, ImpexError>;
 type FinalBiomeConfig = PolkadotConfig;
 type Client = OnlineClient<FinalBiomeConfig>;
 
-type BoundedVec<T> = runtime_types::sp_runtime::bounded::bounded_vec::BoundedVec<T>;
-
 pub(crate) type OrganizationDetails =
   runtime_types::pallet_organization_identity::types::OrganizationDetails<BoundedVec<u8>>;
 
 pub(crate) type FungibleAssetDetails =
   runtime_types::pallet_fungible_assets::types::AssetDetails<AccountId32, BoundedVec<u8>>;
-pub(crate) type FungibleAssetId =
-  runtime_types::pallet_support::types::fungible_asset_id::FungibleAssetId;
-
-pub(crate) type NonFungibleClassId =
-  runtime_types::pallet_support::types::non_fungible_class_id::NonFungibleClassId;
+pub(crate) use handle::{FungibleAssetId, NonFungibleClassId};
 
 pub(crate) type NonFungibleDetails =
   runtime_types::pallet_support::types_nfa::ClassDetails<AccountId32>;
 
 pub(crate) type FungibleAssetIds = Vec<(FungibleAssetId, FungibleAssetDetails)>;
+
+pub(crate) type FungibleAssetBalance =
+  runtime_types::pallet_support::types::fungible_asset_balance::FungibleAssetBalance;
+
+pub(crate) type FungibleAssetBalances = Vec<(AccountId32, FungibleAssetId, FungibleAssetBalance)>;
 pub(crate) type NonFungibleClassDetails = Vec<(NonFungibleClassId, NonFungibleDetails)>;
 pub(crate) type AttributeKey = BoundedVec<u8>;
+pub(crate) type NonFungibleAssetId =
+  runtime_types::pallet_support::types::non_fungible_asset_id::NonFungibleAssetId;
+pub(crate) type NonFungibleAssetDetails =
+  runtime_types::pallet_support::types_nfa::AssetDetails<AccountId32, u32>;
 pub(crate) type AttributesDetails = Vec<(
   NonFungibleClassId,
   AttributeKey,
   runtime_types::pallet_support::AttributeValue,
 )>;
 
-type AirDropAsset = runtime_types::pallet_organization_identity::types::AirDropAsset;
+pub(crate) type AirDropAsset = runtime_types::pallet_organization_identity::types::AirDropAsset;
+
+/// Block of the network at which the state is read
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum BlockAt {
+  /// The latest best block
+  #[default]
+  Best,
+  /// The latest finalized block
+  Finalized,
+  /// Block with the given number
+  Number(u32),
+  /// Block with the given hash in hex
+  Hash(String),
+}
+
+impl std::str::FromStr for BlockAt {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "best" => Ok(BlockAt::Best),
+      "finalized" => Ok(BlockAt::Finalized),
+      s if s.starts_with("0x") => Ok(BlockAt::Hash(s.to_string())),
+      s => s.parse().map(BlockAt::Number).map_err(|_| {
+        format!(
+          "Expected a block number or a 0x-prefixed block hash, got {}",
+          s
+        )
+      }),
+    }
+  }
+}
+
+/// Options of fetching the game from the network
+#[derive(Clone, Debug)]
+pub struct FetchOptions {
+  /// Number of storage keys requested at once
+  pub page_size: u32,
+  /// Number of requests sent to the node concurrently
+  pub parallelism: usize,
+}
+
+impl Default for FetchOptions {
+  fn default() -> Self {
+    FetchOptions {
+      page_size: 100,
+      parallelism: 8,
+    }
+  }
+}
+
+/// Options of submitting the calls to the network
+#[derive(Clone, Debug)]
+pub struct SubmitOptions {
+  /// Number of calls of the same signer submitted without waiting for their inclusion.
+  ///
+  /// Calls which need the ids of the assets created by the calls in flight wait for them.
+  pub window: usize,
+  /// Max number of calls grouped into one `Utility::batch_all` extrinsic, 1 disables batching.
+  ///
+  /// Member additions, attribute creations and characteristic updates are batched if the
+  /// runtime has the `Utility` pallet, otherwise they are submitted one by one.
+  pub batch_size: usize,
+  /// Number of times a transaction is submitted again after a transient failure.
+  ///
+  /// See [`ImpexError::is_retryable`], the calls failed in the runtime are not retried.
+  pub retries: u32,
+  /// Delay before the first retry, doubled for each next one
+  pub backoff: Duration,
+  /// Time in which the transaction must be included in a block, otherwise it's failed
+  pub timeout: Duration,
+}
+
+impl Default for SubmitOptions {
+  fn default() -> Self {
+    SubmitOptions {
+      window: 16,
+      batch_size: 32,
+      retries: 3,
+      backoff: Duration::from_secs(2),
+      timeout: Duration::from_secs(60),
+    }
+  }
+}
+
+/// Options of the game spec export
+#[derive(Clone, Default)]
+pub struct ExportOptions {
+  /// Overwrite the file if it exists
+  pub overwrite: bool,
+  /// Format of the file, detected by the extension of the file if not set
+  pub format: Option<SpecFormat>,
+  /// Block at which the game is exported
+  pub at: BlockAt,
+  /// Options of fetching the game from the network
+  pub fetch: FetchOptions,
+  /// Export the users (players) of the game
+  pub include_users: bool,
+  /// Export the FA balances of the users of the game
+  pub include_balances: bool,
+  /// Export the minted NFA instances with their owners and attributes
+  pub include_instances: bool,
+}
 
 /// Export game spec to file.
 ///
@@ -152,132 +300,1107 @@ type AirDropAsset = runtime_types::pallet_organization_identity::types::AirDropA
 /// - Organization Details
 /// - Organization Members
 /// - Users of orgamization
+///
+/// If the format is not set, it is detected by the extension of the file.
 pub async fn export_game_spec(
   endpoint: String,
   organization: String,
   game_spec_path: PathBuf,
-  overwrite_file: bool,
+  options: ExportOptions,
 ) -> ResultOf<()> {
   // init api client
   let api = Client::from_url(endpoint).await?;
-  // get current hash
-  let block_hash = fetch_curr_hash(&api).await?;
   // set organization account id from SS58 address
   let organization_id: AccountId32 =
     public_from_uri::<sp_core::sr25519::Pair>(&organization)?.into();
-
-  let node_version = fetch_node_version(&api);
-  let org_details = fetch_organization_details(&api, &organization_id, block_hash);
-  let org_members = fetch_organization_members(&api, &organization_id, block_hash);
-  let fas = fetch_fas(&api, &organization_id, block_hash);
-  let nfas = fetch_nfas(&api, &organization_id, block_hash);
-  let attrs = fetch_nfa_attributes(&api, &organization_id, block_hash);
-
-  let game_spec_builder = GameSpecBuilder::new();
-  let game_spec = game_spec_builder
-    .version(node_version.await?)
-    .hash(format!("0x{}", HexDisplay::from(&block_hash.as_ref())))
-    .organization_details(org_details.await?)
-    .organization_members(org_members.await?)
-    .fa(fas.await?)
-    .nfa(nfas.await?)
-    .attributes(attrs.await?)
-    .try_build()?;
+  let game_spec = export_game(&api, &organization_id, &options).await?;
 
   // save to file
-  if !overwrite_file {
+  if !options.overwrite {
     // check file exists
     if File::open(&game_spec_path).is_ok() {
-      return Err(format!("File {} already exists", game_spec_path.display()).into());
+      return Err(ImpexError::InvalidArgument(format!(
+        "File {} already exists",
+        game_spec_path.display()
+      )));
     }
   }
-  let f = File::create(game_spec_path)?;
-  serde_json::to_writer(f, &game_spec)?;
+  let format = options
+    .format
+    .unwrap_or_else(|| SpecFormat::from_path(&game_spec_path));
+  save_game_spec(game_spec_path, &game_spec, format)?;
 
   println!("Game spec has been exported");
 
   Ok(())
 }
 
+/// Fetch the game spec of the organization from the chain.
+///
+/// The users, balances and instances are fetched according to the options, the file options are
+/// ignored.
+pub async fn export_game<B>(
+  api: &B,
+  organization_id: &AccountId32,
+  options: &ExportOptions,
+) -> ResultOf<GameSpec>
+where
+  B: ChainBackend + ?Sized,
+{
+  // get the hash of the block to export from
+  let block_hash = api.block_hash(&options.at).await?;
+
+  let mut game_spec = fetch_game_spec(api, organization_id, block_hash, &options.fetch).await?;
+  if options.include_users || options.include_balances {
+    let users = fetch_organization_users(api, organization_id, block_hash, &options.fetch).await?;
+    if options.include_balances {
+      let fa_ids: Vec<_> = game_spec.fa.iter().map(|(fa_id, _)| *fa_id).collect();
+      game_spec.balances =
+        Some(fetch_fa_balances(api, &users, &fa_ids, block_hash, &options.fetch).await?);
+    }
+    if options.include_users {
+      game_spec.users = Some(users);
+    }
+  }
+  if options.include_instances {
+    let class_ids: Vec<_> = game_spec
+      .nfa
+      .iter()
+      .map(|(class_id, _)| *class_id)
+      .collect();
+    game_spec.instances =
+      Some(fetch_nfa_instances(api, &class_ids, block_hash, &options.fetch).await?);
+  }
+  Ok(game_spec)
+}
+
+/// Options of the game inspection
+#[derive(Clone, Default)]
+pub struct InspectOptions {
+  /// Block at which the game is inspected
+  pub at: BlockAt,
+  /// Options of fetching the game from the network
+  pub fetch: FetchOptions,
+}
+
+/// Inspect the running state of the game: mechanics started by its users and pending top-ups.
+///
+/// Helps to find stuck mechanics, e.g. bets which are not finished after their timeout.
+pub async fn inspect_game(
+  endpoint: String,
+  organization: String,
+  options: InspectOptions,
+) -> ResultOf<GameInspection> {
+  // init api client
+  let api = Client::from_url(endpoint).await?;
+  let block_hash = api.block_hash(&options.at).await?;
+  let block_number = api.block_number(block_hash).await?;
+  let organization_id: AccountId32 =
+    public_from_uri::<sp_core::sr25519::Pair>(&organization)?.into();
+
+  let mechanics = async {
+    let users =
+      fetch_organization_users(&api, &organization_id, block_hash, &options.fetch).await?;
+    fetch_mechanics(&api, &organization_id, &users, block_hash, &options.fetch).await
+  };
+  let top_ups = async {
+    let fas = fetch_fas(&api, &organization_id, block_hash, &options.fetch).await?;
+    let fa_ids: Vec<_> = fas.iter().map(|(fa_id, _)| *fa_id).collect();
+    fetch_top_ups(&api, &fa_ids, block_hash, &options.fetch).await
+  };
+  let (mechanics, (top_upped_fa, top_up_queue)) = try_join!(mechanics, top_ups)?;
+
+  Ok(GameInspection {
+    organization: organization_id,
+    hash: format!("0x{}", HexDisplay::from(&block_hash.as_ref())),
+    block_number,
+    mechanics,
+    top_upped_fa,
+    top_up_queue,
+  })
+}
+
+/// Options of the game spec import
+#[derive(Clone, Default)]
+pub struct ImportOptions {
+  /// Path to the journal file in which the progress of the import is recorded.
+  ///
+  /// If not set, the journal is written next to the game spec file.
+  pub journal: Option<PathBuf>,
+  /// Continue the import recorded in the journal instead of starting a new one
+  pub resume: bool,
+  /// Seed template of the player accounts onboarded to the game, e.g. `//Player/{n}`.
+  ///
+  /// `{n}` is replaced with the number of the player, one player is onboarded for each user of
+  /// the game spec.
+  pub players: Option<String>,
+  /// Options of submitting the calls
+  pub submit: SubmitOptions,
+  /// Destroy the assets created by the import if it fails, instead of keeping them for resume
+  pub teardown_on_failure: bool,
+  /// Import the game without the FA balances of the spec.
+  ///
+  /// The runtime has no call to issue or transfer FA, so the import of a spec with balances
+  /// fails unless they are skipped explicitly.
+  pub skip_balances: bool,
+  /// Import the game without the minted NFA instances of the spec.
+  ///
+  /// The runtime has no call to mint NFA for a given owner, so the import of a spec with
+  /// instances fails unless they are skipped explicitly.
+  pub skip_instances: bool,
+}
+
 /// Import game spec into the network.
+///
+/// If the organization already exists, only the members and assets of the spec which are missing
+/// in the network are created, so the import can be safely run again.
+/// Each completed step is recorded in the journal, so a failed import can be continued with
+/// [`ImportOptions::resume`]. Returns the ids of the created assets and the results of the
+/// submitted calls.
 pub async fn import_game_spec(
   endpoint: String,
   game_spec_path: PathBuf,
   organization_seed: String,
   manager_seed: String,
-) -> ResultOf<()> {
-  // init api client
-  let api = Client::from_url(endpoint).await?;
+  options: ImportOptions,
+) -> ResultOf<ImportReport> {
+  let journal_path = options
+    .journal
+    .clone()
+    .unwrap_or_else(|| game_spec_path.with_extension("journal.json"));
   // load game spec from file
   let game_spec = load_game_spec(&game_spec_path)?;
+  check_unsupported(&game_spec, &options)?;
+  // init api client
+  let api = Client::from_url(endpoint).await?;
+  // build the sequence of calls and load or create the journal
+  let (plan, mut journal) = if options.resume {
+    // the plan is built from the state of the game before the import, which is kept in the journal
+    let journal = ImportJournal::load(&journal_path)?;
+    let plan = build_import_plan(
+      &game_spec,
+      &organization_seed,
+      &manager_seed,
+      options.players.as_deref(),
+      journal.existing.as_ref(),
+    )?;
+    journal.check(&journal_path, &plan)?;
+    (plan, journal)
+  } else {
+    let existing = fetch_existing_game(&api, &organization_seed).await?;
+    // the plan of the existing game is built from its current state, so the journal of the
+    // previous run is not needed
+    if existing.is_none() && journal_path.exists() {
+      return Err(ImpexError::InvalidArgument(format!(
+        "Journal {} already exists, resume the import or remove it",
+        journal_path.display()
+      )));
+    }
+    let plan = build_import_plan(
+      &game_spec,
+      &organization_seed,
+      &manager_seed,
+      options.players.as_deref(),
+      existing.as_ref(),
+    )?;
+    let mut journal = ImportJournal::new(&plan)?;
+    journal.existing = existing;
+    (plan, journal)
+  };
+  let result = submit_import(
+    &api,
+    &plan,
+    &organization_seed,
+    &manager_seed,
+    options.players.as_deref(),
+    &mut journal,
+    Some(&journal_path),
+    &options.submit,
+  )
+  .await;
+  let report = match result {
+    Ok(report) => report,
+    Err(e) if options.teardown_on_failure => {
+      eprintln!("Import failed: {}", e);
+      let teardown = teardown_import(
+        &api,
+        &plan,
+        &journal,
+        &organization_seed,
+        &manager_seed,
+        &options.submit,
+      )
+      .await;
+      return match teardown {
+        Ok(_) => {
+          // the created assets are destroyed, so the import cannot be resumed
+          remove_journal(&journal_path)?;
+          println!("Items created by the import have been torn down");
+          Err(e)
+        },
+        Err(teardown_error) => {
+          eprintln!("Teardown failed: {}", teardown_error);
+          Err(ImpexError::Interrupted {
+            journal: journal_path,
+            source: Box::new(e),
+          })
+        },
+      };
+    },
+    Err(e) => {
+      return Err(ImpexError::Interrupted {
+        journal: journal_path,
+        source: Box::new(e),
+      })
+    },
+  };
+  // the import is complete, the journal would only block the next import of the spec
+  remove_journal(&journal_path)?;
+
+  println!("Game spec has been imported to the network");
+  Ok(report)
+}
+
+/// Removes the journal of the import if it exists
+fn remove_journal(journal_path: &Path) -> ResultOf<()> {
+  if journal_path.exists() {
+    fs::remove_file(journal_path).map_err(|e| ImpexError::file(journal_path, e))?;
+  }
+  Ok(())
+}
+
+/// Import the game spec into the chain without a journal.
+///
+/// If the organization already exists, only the missing items of the spec are created.
+/// Players are onboarded with the seed template of the options if it is given, the journal
+/// options are not used. With [`ImportOptions::teardown_on_failure`] the items created by the
+/// failed import are torn down before the error is returned.
+pub async fn import_game<B>(
+  api: &B,
+  game_spec: &GameSpec,
+  organization_seed: &str,
+  manager_seed: &str,
+  options: &ImportOptions,
+) -> ResultOf<ImportReport>
+where
+  B: ChainBackend + ?Sized,
+{
+  check_unsupported(game_spec, options)?;
+  let existing = fetch_existing_game(api, organization_seed).await?;
+  let plan = build_import_plan(
+    game_spec,
+    organization_seed,
+    manager_seed,
+    options.players.as_deref(),
+    existing.as_ref(),
+  )?;
+  let mut journal = ImportJournal::new(&plan)?;
+  let result = submit_import(
+    api,
+    &plan,
+    organization_seed,
+    manager_seed,
+    options.players.as_deref(),
+    &mut journal,
+    None,
+    &options.submit,
+  )
+  .await;
+  if let Err(e) = result {
+    if options.teardown_on_failure {
+      // the error of the import is returned, the failed teardown is only reported
+      let teardown = teardown_import(
+        api,
+        &plan,
+        &journal,
+        organization_seed,
+        manager_seed,
+        &options.submit,
+      )
+      .await;
+      if let Err(teardown_error) = teardown {
+        eprintln!("Teardown failed: {}", teardown_error);
+      }
+    }
+    return Err(e);
+  }
+  result
+}
+
+/// Submit the calls of the import plan to the network, the progress is recorded in the journal
+#[allow(clippy::too_many_arguments)]
+async fn submit_import<B>(
+  api: &B,
+  plan: &ImportPlan,
+  organization_seed: &str,
+  manager_seed: &str,
+  players: Option<&str>,
+  journal: &mut ImportJournal,
+  journal_path: Option<&Path>,
+  options: &SubmitOptions,
+) -> ResultOf<ImportReport>
+where
+  B: ChainBackend + ?Sized,
+{
+  for note in &plan.notes {
+    println!("Note: {}", note);
+  }
   // construst the game signer
-  let organization_pair = pair_from_suri::<sp_core::sr25519::Pair>(&organization_seed, None)?;
-  let organization_signer = PairSigner::new(organization_pair);
+  let organization_signer = pair_from_suri::<sr25519::Pair>(organization_seed, None)?;
   // construst the manager signer
-  let manager_pair = pair_from_suri::<sp_core::sr25519::Pair>(&manager_seed, None)?;
-  let manager_signer = PairSigner::new(manager_pair.clone());
+  let manager_signer = pair_from_suri::<sr25519::Pair>(manager_seed, None)?;
+  // construst the player signers
+  let mut player_signers = HashMap::new();
+  for (n, seed) in player_seeds(plan, players)? {
+    player_signers.insert(n, pair_from_suri::<sr25519::Pair>(&seed, None)?);
+  }
   // create game in the network
-  post_to_node::<FinalBiomeConfig, sp_core::sr25519::Pair>(
+  post_to_node(
+    api,
+    plan,
+    Some(organization_signer),
+    manager_signer,
+    player_signers,
+    journal,
+    journal_path,
+    options,
+  )
+  .await
+}
+
+/// Tear down the items created by the failed import, they are found by the ids in the journal.
+///
+/// The onboarding assets are cleared if they refer to the created assets. Members added by the
+/// import are removed.
+async fn teardown_import<B>(
+  api: &B,
+  plan: &ImportPlan,
+  journal: &ImportJournal,
+  organization_seed: &str,
+  manager_seed: &str,
+  options: &SubmitOptions,
+) -> ResultOf<ImportReport>
+where
+  B: ChainBackend + ?Sized,
+{
+  let completed = |n: usize| n < journal.completed_steps || journal.completed_ahead.contains(&n);
+  if !(0..plan.steps.len()).any(completed) {
+    return Ok(ImportReport::new(plan.organization.clone(), journal));
+  }
+  let created_fa: Vec<FungibleAssetId> = journal
+    .fa_ids_map
+    .iter()
+    .filter(|ids| !plan.fa_ids_map.contains(ids))
+    .map(|(_, id)| *id)
+    .collect();
+  let created_nfa: Vec<NonFungibleClassId> = journal
+    .nfa_ids_map
+    .iter()
+    .filter(|ids| !plan.nfa_ids_map.contains(ids))
+    .map(|(_, id)| *id)
+    .collect();
+  let added_members: Vec<AccountId32> = plan
+    .steps
+    .iter()
+    .enumerate()
+    .filter(|(n, _)| completed(*n))
+    .filter_map(|(_, step)| match &step.call {
+      ImportCall::AddMember { who } => Some(who.clone()),
+      _ => None,
+    })
+    .collect();
+
+  // keep only the items created by the import in the state to tear down
+  let block_hash = fetch_curr_hash(api).await?;
+  let mut live_spec = fetch_game_spec(
+    api,
+    &plan.organization,
+    block_hash,
+    &FetchOptions::default(),
+  )
+  .await?;
+  live_spec.fa.retain(|(id, _)| created_fa.contains(id));
+  live_spec.nfa.retain(|(id, _)| created_nfa.contains(id));
+  live_spec
+    .attributes
+    .retain(|(class, ..)| created_nfa.contains(class));
+  live_spec
+    .organization_members
+    .retain(|who| added_members.contains(who));
+  let refers_created = live_spec
+    .organization_details
+    .onboarding_assets
+    .as_ref()
+    .is_some_and(|assets| {
+      assets.0.iter().any(|asset| match asset {
+        AirDropAsset::Fa(id, _) => created_fa.contains(id),
+        AirDropAsset::Nfa(id, _) => created_nfa.contains(id),
+      })
+    });
+  if !refers_created {
+    live_spec.organization_details.onboarding_assets = None;
+  }
+
+  let plan = ImportPlan::teardown(
+    &live_spec,
+    plan.organization.clone(),
+    plan.manager.clone(),
+    true,
+  );
+  let organization_signer = pair_from_suri::<sr25519::Pair>(organization_seed, None)?;
+  let manager_signer = pair_from_suri::<sr25519::Pair>(manager_seed, None)?;
+  let mut journal = ImportJournal::new(&plan)?;
+  post_to_node(
+    api,
+    &plan,
+    Some(organization_signer),
+    manager_signer,
+    HashMap::new(),
+    &mut journal,
+    None,
+    options,
+  )
+  .await
+}
+
+/// Options of the game cloning
+#[derive(Clone, Default)]
+pub struct CloneOptions {
+  /// Block of the source network at which the game is exported
+  pub at: BlockAt,
+  /// Options of fetching the game from the source network
+  pub fetch: FetchOptions,
+  /// Clone the users (players) of the game, see [`CloneOptions::players`]
+  pub include_users: bool,
+  /// Seed template of the player accounts onboarded to the cloned game, e.g. `//Player/{n}`
+  pub players: Option<String>,
+  /// Path to the file to which the exported game spec is saved.
+  ///
+  /// If set, the progress is recorded in the journal next to it, so a failed clone can be
+  /// continued by the import of the saved spec.
+  pub game_spec: Option<PathBuf>,
+  /// Options of submitting the calls to the target network
+  pub submit: SubmitOptions,
+}
+
+/// Clone the game from one network to another without an intermediate file.
+///
+/// The game is exported from the source network and imported into the target one on behalf of
+/// the given seeds. Returns the ids of the created assets and the results of the submitted calls.
+pub async fn clone_game(
+  from_endpoint: String,
+  organization: String,
+  to_endpoint: String,
+  organization_seed: String,
+  manager_seed: String,
+  options: CloneOptions,
+) -> ResultOf<ImportReport> {
+  // export the game from the source network
+  let from_api = Client::from_url(from_endpoint).await?;
+  let block_hash = from_api.block_hash(&options.at).await?;
+  let organization_id: AccountId32 =
+    public_from_uri::<sp_core::sr25519::Pair>(&organization)?.into();
+  let mut game_spec =
+    fetch_game_spec(&from_api, &organization_id, block_hash, &options.fetch).await?;
+  if options.include_users {
+    game_spec.users = Some(
+      fetch_organization_users(&from_api, &organization_id, block_hash, &options.fetch).await?,
+    );
+  }
+  // the journal is kept next to the saved spec
+  let journal_path = options
+    .game_spec
+    .as_ref()
+    .map(|path| path.with_extension("journal.json"));
+  if let Some(path) = &options.game_spec {
+    if path.exists() {
+      return Err(ImpexError::InvalidArgument(format!(
+        "File {} already exists",
+        path.display()
+      )));
+    }
+    if let Some(journal_path) = journal_path.as_ref().filter(|path| path.exists()) {
+      return Err(ImpexError::InvalidArgument(format!(
+        "Journal {} already exists",
+        journal_path.display()
+      )));
+    }
+    save_game_spec(path, &game_spec, SpecFormat::from_path(path))?;
+    println!("Game spec has been saved to {}", path.display());
+  }
+
+  // import the game into the target network
+  let to_api = Client::from_url(to_endpoint).await?;
+  let existing = fetch_existing_game(&to_api, &organization_seed).await?;
+  let plan = build_import_plan(
+    &game_spec,
+    &organization_seed,
+    &manager_seed,
+    options.players.as_deref(),
+    existing.as_ref(),
+  )?;
+  let mut journal = ImportJournal::new(&plan)?;
+  journal.existing = existing;
+  let report = submit_import(
+    &to_api,
+    &plan,
+    &organization_seed,
+    &manager_seed,
+    options.players.as_deref(),
+    &mut journal,
+    journal_path.as_deref(),
+    &options.submit,
+  )
+  .await
+  .map_err(|e| match &journal_path {
+    Some(journal_path) => ImpexError::Interrupted {
+      journal: journal_path.clone(),
+      source: Box::new(e),
+    },
+    None => e,
+  })?;
+  if let Some(journal_path) = &journal_path {
+    remove_journal(journal_path)?;
+  }
+
+  println!("Game has been cloned to the network");
+  Ok(report)
+}
+
+/// Build the ordered list of calls which import of the game spec would submit.
+///
+/// Nothing is signed or submitted. If the endpoint is given, the game is fetched from the
+/// network, so the plan of an existing organization creates only its missing items as the import
+/// does. Without the endpoint no connection to the network is made and the plan creates the whole
+/// game. The plan of the resumed import is built from the state of the game kept in its journal.
+pub async fn plan_import(
+  endpoint: Option<String>,
+  game_spec_path: PathBuf,
+  organization_seed: String,
+  manager_seed: String,
+  options: &ImportOptions,
+) -> ResultOf<ImportPlan> {
+  // load game spec from file
+  let game_spec = load_game_spec(&game_spec_path)?;
+  check_unsupported(&game_spec, options)?;
+  let existing = match endpoint {
+    // the resumed import is planned from the state of the game kept in the journal
+    _ if options.resume => {
+      let journal_path = options
+        .journal
+        .clone()
+        .unwrap_or_else(|| game_spec_path.with_extension("journal.json"));
+      ImportJournal::load(journal_path)?.existing
+    },
+    Some(endpoint) => {
+      // init api client
+      let api = Client::from_url(endpoint).await?;
+      fetch_existing_game(&api, &organization_seed).await?
+    },
+    None => None,
+  };
+  build_import_plan(
+    &game_spec,
+    &organization_seed,
+    &manager_seed,
+    options.players.as_deref(),
+    existing.as_ref(),
+  )
+}
+
+/// Fails if the game spec holds the state which the import cannot recreate, unless the options
+/// skip it
+fn check_unsupported(game_spec: &GameSpec, options: &ImportOptions) -> ResultOf<()> {
+  if let Some(balances) = game_spec.balances.as_ref().filter(|b| !b.is_empty()) {
+    if !options.skip_balances {
+      return Err(ImpexError::Spec(format!(
+        "Game spec has {} FA balances, which cannot be imported: the runtime has no call to \
+         issue or transfer FA. Skip the balances to import the rest of the game",
+        balances.len()
+      )));
+    }
+  }
+  if let Some(instances) = game_spec.instances.as_ref().filter(|i| !i.is_empty()) {
+    if !options.skip_instances {
+      return Err(ImpexError::Spec(format!(
+        "Game spec has {} NFA instances, which cannot be imported: the runtime has no call to \
+         mint NFA. Skip the instances to import the rest of the game",
+        instances.len()
+      )));
+    }
+  }
+  Ok(())
+}
+
+/// Build the ordered list of calls which create the game of the spec.
+///
+/// If the game already exists in the network, only the missing items are created.
+fn build_import_plan(
+  game_spec: &GameSpec,
+  organization_seed: &str,
+  manager_seed: &str,
+  players: Option<&str>,
+  existing: Option<&ExistingGame>,
+) -> ResultOf<ImportPlan> {
+  let organization_pair = pair_from_suri::<sp_core::sr25519::Pair>(organization_seed, None)?;
+  let manager_pair = pair_from_suri::<sp_core::sr25519::Pair>(manager_seed, None)?;
+
+  let mut plan = match existing {
+    Some(existing) => ImportPlan::complete(
+      game_spec,
+      &existing.spec,
+      organization_pair.public().into(),
+      manager_pair.public().into(),
+    ),
+    None => ImportPlan::new(
+      game_spec,
+      organization_pair.public().into(),
+      manager_pair.public().into(),
+    ),
+  };
+  // onboard a test account for each user of the game
+  if let Some(template) = players {
+    let users = game_spec.users.as_ref().map_or(0, |users| users.len());
+    let mut players = vec![];
+    for n in 0..users {
+      let seed = player_seed(template, n)?;
+      players.push(
+        pair_from_suri::<sp_core::sr25519::Pair>(&seed, None)?
+          .public()
+          .into(),
+      );
+    }
+    let onboarded = existing.map_or(&[][..], |existing| &existing.users[..]);
+    plan.onboard_players(players, onboarded);
+  }
+  Ok(plan)
+}
+
+/// State of the game which already exists in the network
+#[derive(Serialize, Deserialize, Clone)]
+pub(crate) struct ExistingGame {
+  spec: GameSpec,
+  /// Users (players) of the game
+  users: Vec<AccountId32>,
+}
+
+/// Fetch the current state of the game if the organization already exists in the network
+async fn fetch_existing_game<B>(api: &B, organization_seed: &str) -> ResultOf<Option<ExistingGame>>
+where
+  B: ChainBackend + ?Sized,
+{
+  let organization_pair = pair_from_suri::<sp_core::sr25519::Pair>(organization_seed, None)?;
+  let organization_id: AccountId32 = organization_pair.public().into();
+  let address = finalbiome::storage()
+    .organization_identity()
+    .organizations(&organization_id);
+  let block_hash = fetch_curr_hash(api).await?;
+  let organization_details: Option<OrganizationDetails> =
+    fetch_value(api, address.to_bytes(), block_hash).await?;
+  if organization_details.is_none() {
+    return Ok(None);
+  }
+  let options = FetchOptions::default();
+  let (spec, users) = try_join!(
+    fetch_game_spec(api, &organization_id, block_hash, &options),
+    fetch_organization_users(api, &organization_id, block_hash, &options),
+  )?;
+  Ok(Some(ExistingGame { spec, users }))
+}
+
+/// Returns the seed of the player with the given number
+fn player_seed(template: &str, n: usize) -> ResultOf<String> {
+  if !template.contains("{n}") {
+    return Err(ImpexError::InvalidArgument(format!(
+      "Player seed template {} does not contain {{n}}",
+      template
+    )));
+  }
+  Ok(template.replace("{n}", &n.to_string()))
+}
+
+/// Returns the seeds of the players onboarded by the plan by their numbers
+fn player_seeds(plan: &ImportPlan, template: Option<&str>) -> ResultOf<HashMap<usize, String>> {
+  let mut seeds = HashMap::new();
+  for step in &plan.steps {
+    if let StepSigner::Player(n) = step.signer {
+      let template = template.ok_or_else(|| {
+        ImpexError::InvalidArgument("Player seed template is not set".to_string())
+      })?;
+      seeds.insert(n, player_seed(template, n)?);
+    }
+  }
+  Ok(seeds)
+}
+
+/// Build the list of calls which brings the game existing in the network to the state of the
+/// game spec.
+pub async fn plan_apply(
+  endpoint: String,
+  game_spec_path: PathBuf,
+  organization_seed: String,
+  manager_seed: String,
+) -> ResultOf<ImportPlan> {
+  // load game spec from file
+  let game_spec = load_game_spec(&game_spec_path)?;
+  // init api client
+  let api = Client::from_url(endpoint).await?;
+  plan_apply_with(&api, &game_spec, &organization_seed, &manager_seed).await
+}
+
+/// Update the game existing in the network to the state of the game spec.
+///
+/// Only the differences between the game spec and the game in the network are submitted.
+/// Returns the ids of the spec assets in the network and the results of the submitted calls.
+pub async fn apply_game_spec(
+  endpoint: String,
+  game_spec_path: PathBuf,
+  organization_seed: String,
+  manager_seed: String,
+  options: SubmitOptions,
+) -> ResultOf<ImportReport> {
+  // load game spec from file
+  let game_spec = load_game_spec(&game_spec_path)?;
+  // init api client
+  let api = Client::from_url(endpoint).await?;
+  let report = apply_game(
     &api,
+    &game_spec,
+    &organization_seed,
+    &manager_seed,
+    &options,
+  )
+  .await?;
+
+  println!("Game spec has been applied to the network");
+  Ok(report)
+}
+
+/// Update the game existing in the chain to the state of the game spec.
+///
+/// Only the differences between the game spec and the game in the chain are submitted.
+pub async fn apply_game<B>(
+  api: &B,
+  game_spec: &GameSpec,
+  organization_seed: &str,
+  manager_seed: &str,
+  options: &SubmitOptions,
+) -> ResultOf<ImportReport>
+where
+  B: ChainBackend + ?Sized,
+{
+  let plan = plan_apply_with(api, game_spec, organization_seed, manager_seed).await?;
+  for note in &plan.notes {
+    println!("Note: {}", note);
+  }
+  // the plan is built from the current state, so it's enough to apply the spec again if it fails
+  let mut journal = ImportJournal::new(&plan)?;
+  if plan.steps.is_empty() {
+    println!("Game is up to date");
+    return Ok(ImportReport::new(plan.organization, &journal));
+  }
+  // construst the game signer
+  let organization_signer = pair_from_suri::<sr25519::Pair>(organization_seed, None)?;
+  // construst the manager signer
+  let manager_signer = pair_from_suri::<sr25519::Pair>(manager_seed, None)?;
+  post_to_node(
+    api,
+    &plan,
+    Some(organization_signer),
+    manager_signer,
+    HashMap::new(),
+    &mut journal,
+    None,
+    options,
+  )
+  .await
+}
+
+async fn plan_apply_with<B>(
+  api: &B,
+  game_spec: &GameSpec,
+  organization_seed: &str,
+  manager_seed: &str,
+) -> ResultOf<ImportPlan>
+where
+  B: ChainBackend + ?Sized,
+{
+  let organization_pair = pair_from_suri::<sp_core::sr25519::Pair>(organization_seed, None)?;
+  let manager_pair = pair_from_suri::<sp_core::sr25519::Pair>(manager_seed, None)?;
+  let organization_id: AccountId32 = organization_pair.public().into();
+  // fetch the current state of the game
+  let block_hash = fetch_curr_hash(api).await?;
+  let live_spec =
+    fetch_game_spec(api, &organization_id, block_hash, &FetchOptions::default()).await?;
+
+  Ok(ImportPlan::reconcile(
     game_spec,
+    &live_spec,
+    organization_id,
+    manager_pair.public().into(),
+  ))
+}
+
+/// Options of the game teardown
+#[derive(Clone, Default)]
+pub struct TeardownOptions {
+  /// Organization account key seed, the members are removed only if it's given
+  pub organization_seed: Option<String>,
+  /// Options of submitting the calls
+  pub submit: SubmitOptions,
+}
+
+/// Build the list of calls which clears the onboarding assets, removes the attributes, destroys
+/// the assets and removes the members of the game existing in the network.
+pub async fn plan_teardown(
+  endpoint: String,
+  organization: String,
+  manager_seed: String,
+  options: &TeardownOptions,
+) -> ResultOf<ImportPlan> {
+  // init api client
+  let api = Client::from_url(endpoint).await?;
+  let organization_id: AccountId32 = public_from_uri::<sr25519::Pair>(&organization)?.into();
+  plan_teardown_with(&api, &organization_id, &manager_seed, options).await
+}
+
+/// Destroy the game existing in the network.
+///
+/// The organization itself cannot be removed from the network, it's left without assets and
+/// members. Returns the results of the submitted calls.
+pub async fn teardown(
+  endpoint: String,
+  organization: String,
+  manager_seed: String,
+  options: TeardownOptions,
+) -> ResultOf<ImportReport> {
+  // init api client
+  let api = Client::from_url(endpoint).await?;
+  let organization_id: AccountId32 = public_from_uri::<sr25519::Pair>(&organization)?.into();
+  let report = teardown_game(&api, &organization_id, &manager_seed, &options).await?;
+
+  println!("Game has been torn down");
+  Ok(report)
+}
+
+/// Destroy the game existing in the chain on behalf of the manager.
+///
+/// The members are removed only if the organization seed of the options is given.
+pub async fn teardown_game<B>(
+  api: &B,
+  organization_id: &AccountId32,
+  manager_seed: &str,
+  options: &TeardownOptions,
+) -> ResultOf<ImportReport>
+where
+  B: ChainBackend + ?Sized,
+{
+  let plan = plan_teardown_with(api, organization_id, manager_seed, options).await?;
+  for note in &plan.notes {
+    println!("Note: {}", note);
+  }
+  let organization_signer = match &options.organization_seed {
+    Some(seed) => Some(pair_from_suri::<sr25519::Pair>(seed, None)?),
+    None => None,
+  };
+  let manager_signer = pair_from_suri::<sr25519::Pair>(manager_seed, None)?;
+  // the plan is built from the current state, so it's enough to tear down again if it fails
+  let mut journal = ImportJournal::new(&plan)?;
+  post_to_node(
+    api,
+    &plan,
     organization_signer,
     manager_signer,
+    HashMap::new(),
+    &mut journal,
+    None,
+    &options.submit,
   )
-  .await?;
+  .await
+}
 
-  println!("Game spec has been imported to the network");
-  Ok(())
+async fn plan_teardown_with<B>(
+  api: &B,
+  organization_id: &AccountId32,
+  manager_seed: &str,
+  options: &TeardownOptions,
+) -> ResultOf<ImportPlan>
+where
+  B: ChainBackend + ?Sized,
+{
+  let manager_pair = pair_from_suri::<sr25519::Pair>(manager_seed, None)?;
+  if let Some(seed) = &options.organization_seed {
+    let organization_pair = pair_from_suri::<sr25519::Pair>(seed, None)?;
+    if AccountId32::from(organization_pair.public()) != *organization_id {
+      return Err(ImpexError::InvalidArgument(
+        "Organization seed doesn't match the organization address".to_string(),
+      ));
+    }
+  }
+  // fetch the current state of the game
+  let block_hash = fetch_curr_hash(api).await?;
+  let live_spec =
+    fetch_game_spec(api, organization_id, block_hash, &FetchOptions::default()).await?;
+
+  Ok(ImportPlan::teardown(
+    &live_spec,
+    organization_id.clone(),
+    manager_pair.public().into(),
+    options.organization_seed.is_some(),
+  ))
+}
+
+/// Source from which the game spec is loaded
+#[derive(Clone, Debug)]
+pub enum SpecSource {
+  /// Game spec file
+  File(PathBuf),
+  /// Game existing in the network
+  Network {
+    /// RPC endpoint of the network node
+    endpoint: String,
+    /// Game address in SS58 format
+    organization: String,
+  },
 }
 
-/// Fetch version of the node
-async fn fetch_node_version<T>(api: &OnlineClient<T>) -> ResultOf<String>
+impl SpecSource {
+  /// Load the game spec from the source
+  pub async fn load(&self) -> ResultOf<GameSpec> {
+    match self {
+      SpecSource::File(path) => load_game_spec(path),
+      SpecSource::Network {
+        endpoint,
+        organization,
+      } => {
+        // init api client
+        let api = Client::from_url(endpoint).await?;
+        let block_hash = fetch_curr_hash(&api).await?;
+        let organization_id: AccountId32 =
+          public_from_uri::<sp_core::sr25519::Pair>(organization)?.into();
+        fetch_game_spec(&api, &organization_id, block_hash, &FetchOptions::default()).await
+      },
+    }
+  }
+}
+
+/// Compare two game specs.
+///
+/// Each spec can be loaded from file or fetched from the network.
+pub async fn diff_game_specs(left: SpecSource, right: SpecSource) -> ResultOf<SpecDiff> {
+  let left = left.load().await?;
+  let right = right.load().await?;
+  Ok(left.diff(&right))
+}
+
+/// Upgrade the game spec file to the current format version in place.
+///
+/// Returns `true` if the file was rewritten, `false` if it is already in the current format.
+pub fn migrate_game_spec(game_spec_path: PathBuf) -> ResultOf<bool> {
+  let (game_spec, version) = read_game_spec_file(&game_spec_path)?;
+  if version == SPEC_FORMAT_VERSION {
+    return Ok(false);
+  }
+  save_game_spec(
+    &game_spec_path,
+    &game_spec,
+    SpecFormat::from_path(&game_spec_path),
+  )?;
+  Ok(true)
+}
+
+/// Validate game spec file without connecting to the network.
+///
+/// Returns all problems found in the spec.
+pub fn validate_game_spec(game_spec_path: PathBuf) -> ResultOf<Vec<ValidationProblem>> {
+  let content =
+    fs::read_to_string(&game_spec_path).map_err(|e| ImpexError::file(&game_spec_path, e))?;
+  let format = SpecFormat::from_path(&game_spec_path);
+  match read_game_spec(&content, format) {
+    Ok((game_spec, _version)) => Ok(game_spec.validate()),
+    // the spec which cannot be read is read entry by entry to report all its problems
+    Err(e) => {
+      let problems = validate::read_problems(&content, format);
+      match problems.is_empty() {
+        true => Ok(vec![ValidationProblem::new("$".into(), e.to_string())]),
+        false => Ok(problems),
+      }
+    },
+  }
+}
+
+/// Fetch the full game spec of the organization at the given block
+async fn fetch_game_spec<B>(
+  api: &B,
+  organization_id: &AccountId32,
+  block_hash: H256,
+  options: &FetchOptions,
+) -> ResultOf<GameSpec>
 where
-  T: subxt::Config,
+  B: ChainBackend + ?Sized,
 {
-  api
-    .rpc()
-    .system_version()
-    .await
-    .map_err(|_| "Cannot fetch version of the node".into())
+  let node_version = api.node_version();
+  let org_details = fetch_organization_details(api, organization_id, block_hash);
+  let org_members = fetch_organization_members(api, organization_id, block_hash, options);
+  let fas = fetch_fas(api, organization_id, block_hash, options);
+  // the class ids are fetched once for the classes and their attributes
+  let nfas_and_attrs = async {
+    let class_ids = fetch_nfa_ids(api, organization_id, block_hash, options).await?;
+    try_join!(
+      fetch_nfas(api, &class_ids, block_hash, options),
+      fetch_nfa_attributes(api, &class_ids, block_hash, options),
+    )
+  };
+  let (node_version, org_details, org_members, fas, (nfas, attrs)) =
+    try_join!(node_version, org_details, org_members, fas, nfas_and_attrs)?;
+
+  let game_spec_builder = GameSpecBuilder::new();
+  game_spec_builder
+    .node_version(node_version)
+    .hash(format!("0x{}", HexDisplay::from(&block_hash.as_ref())))
+    .organization_details(org_details)
+    .organization_members(org_members)
+    .fa(fas)
+    .nfa(nfas)
+    .attributes(attrs)
+    .try_build()
 }
 
 // Fetch a concrete block hash to export from. We do this so that if new blocks
 // are produced midway through export, we continue to export at the block
 // we started with and not the new block.
-async fn fetch_curr_hash<T>(api: &OnlineClient<T>) -> ResultOf<T::Hash>
+async fn fetch_curr_hash<B>(api: &B) -> ResultOf<H256>
 where
-  T: subxt::Config,
+  B: ChainBackend + ?Sized,
 {
-  api
-    .rpc()
-    .block_hash(None)
-    .await?
-    .ok_or_else(|| "Cannot fetch current hash".into())
+  api.block_hash(&BlockAt::Best).await
 }
 
 /// Fetch organization details by organization address
-async fn fetch_organization_details<T>(
-  api: &OnlineClient<T>,
+async fn fetch_organization_details<B>(
+  api: &B,
   organization_id: &AccountId32,
-  block_hash: T::Hash,
+  block_hash: H256,
 ) -> ResultOf<OrganizationDetails>
 where
-  T: subxt::Config,
+  B: ChainBackend + ?Sized,
 {
   let address = finalbiome::storage()
     .organization_identity()
     .organizations(organization_id);
-  let organization_details = api.storage().fetch(&address, Some(block_hash)).await?;
-  organization_details.ok_or_else(|| "Organization not found".into())
+  let organization_details: Option<OrganizationDetails> =
+    fetch_value(api, address.to_bytes(), block_hash).await?;
+  organization_details.ok_or_else(|| ImpexError::NotFound("Organization not found".to_string()))
 }
 
-async fn fetch_organization_members<T>(
-  api: &OnlineClient<T>,
+async fn fetch_organization_members<B>(
+  api: &B,
   organization_id: &AccountId32,
-  block_hash: T::Hash,
+  block_hash: H256,
+  options: &FetchOptions,
 ) -> ResultOf<Vec<sp_runtime::AccountId32>>
 where
-  T: subxt::Config,
+  B: ChainBackend + ?Sized,
 {
   // Iterate over the membersOf storage to get all managers of the game
   let key_addr = finalbiome::storage()
@@ -291,26 +1414,87 @@ where
 
   // Iterate over keys at that address and collect values to vec
   let mut members = vec![];
-  let mut iter = AllKeyIter::new(api, query_key, block_hash, 10);
+  let mut iter = AllKeyIter::new(api, query_key, block_hash, options.page_size);
   while let Some(key) = iter.next().await? {
     // we need last 32 bytes - the member id.
-    let member: [u8; 32] = key.0.as_slice()[key.0.len() - 32..]
-      .try_into()
-      .expect("we get last 32 bytes but it is not");
-    let member_id: sp_runtime::AccountId32 = sp_runtime::AccountId32::new(member);
-
-    members.push(member_id);
+    members.push(account_from_key(&key.0)?);
   }
   Ok(members)
 }
 
-async fn fetch_fas<T>(
-  api: &OnlineClient<T>,
+/// Fetch the users (players) of the organization
+async fn fetch_organization_users<B>(
+  api: &B,
+  organization_id: &AccountId32,
+  block_hash: H256,
+  options: &FetchOptions,
+) -> ResultOf<Vec<sp_runtime::AccountId32>>
+where
+  B: ChainBackend + ?Sized,
+{
+  // Iterate over the usersOf storage to get all users of the game
+  let key_addr = finalbiome::storage()
+    .organization_identity()
+    .users_of_root();
+  // Obtain the root bytes
+  let mut query_key = key_addr.to_root_bytes();
+  // We know that the first key is a AccountId32 and is hashed by Blake2_128Concat.
+  // We can build a `StorageMapKey` that replicates that, and append those bytes to the above.
+  StorageMapKey::new(organization_id, StorageHasher::Blake2_128Concat).to_bytes(&mut query_key);
+
+  // Iterate over keys at that address and collect values to vec
+  let mut users = vec![];
+  let mut iter = AllKeyIter::new(api, query_key, block_hash, options.page_size);
+  while let Some(key) = iter.next().await? {
+    // we need last 32 bytes - the user id.
+    users.push(account_from_key(&key.0)?);
+  }
+  Ok(users)
+}
+
+/// Fetch the balances of the given accounts in the given FA, zero balances are skipped
+async fn fetch_fa_balances<B>(
+  api: &B,
+  accounts: &[AccountId32],
+  fa_ids: &[FungibleAssetId],
+  block_hash: H256,
+  options: &FetchOptions,
+) -> ResultOf<FungibleAssetBalances>
+where
+  B: ChainBackend + ?Sized,
+{
+  let account_fas: Vec<(&AccountId32, &FungibleAssetId)> = accounts
+    .iter()
+    .flat_map(|account| fa_ids.iter().map(move |fa_id| (account, fa_id)))
+    .collect();
+  let keys = account_fas
+    .iter()
+    .map(|(account, fa_id)| {
+      finalbiome::storage()
+        .fungible_assets()
+        .accounts(*account, *fa_id)
+        .to_bytes()
+    })
+    .collect();
+  let asset_accounts: Vec<Option<runtime_types::pallet_fungible_assets::types::AssetAccount>> =
+    fetch_values(api, keys, block_hash, options).await?;
+  let mut balances = vec![];
+  for ((account, fa_id), asset_account) in account_fas.into_iter().zip(asset_accounts) {
+    if let Some(asset_account) = asset_account.filter(|a| a.balance.0 > 0) {
+      balances.push((account.clone(), *fa_id, asset_account.balance));
+    }
+  }
+  Ok(balances)
+}
+
+async fn fetch_fas<B>(
+  api: &B,
   organization_id: &AccountId32,
-  block_hash: T::Hash,
+  block_hash: H256,
+  options: &FetchOptions,
 ) -> ResultOf<Vec<(FungibleAssetId, FungibleAssetDetails)>>
 where
-  T: subxt::Config,
+  B: ChainBackend + ?Sized,
 {
   // 1. Fetch the fa ids belonging to the game
   let mut asset_ids = vec![];
@@ -322,25 +1506,29 @@ where
   // We can build a `StorageMapKey` that replicates that, and append those bytes to the above.
   StorageMapKey::new(organization_id, StorageHasher::Blake2_128Concat).to_bytes(&mut query_key);
   // Iterate over keys at that address and collect values to vec
-  let mut iter = AllKeyIter::new(api, query_key, block_hash, 10);
+  let mut iter = AllKeyIter::new(api, query_key, block_hash, options.page_size);
   while let Some(key) = iter.next().await? {
     // we need last 4 bytes (u32) - the asset id.
-    let asset_id_encoded = key.0.as_slice()[key.0.len() - 4..].to_vec();
-    let asset_id = FungibleAssetId::decode(&mut &*asset_id_encoded)?;
+    let asset_id = FungibleAssetId::decode(&mut key_suffix(&key.0, 4)?)?;
 
     asset_ids.push(asset_id);
   }
 
   // 2. Fetch details about each found assets
+  let keys = asset_ids
+    .iter()
+    .map(|asset_id| {
+      finalbiome::storage()
+        .fungible_assets()
+        .assets(asset_id)
+        .to_bytes()
+    })
+    .collect();
+  let details = fetch_values::<_, FungibleAssetDetails>(api, keys, block_hash, options).await?;
   let mut fa_details = vec![];
-  for asset_id in asset_ids {
-    let address = finalbiome::storage().fungible_assets().assets(asset_id);
-    let details: FungibleAssetDetails = api
-      .storage()
-      .fetch(&address, Some(block_hash))
-      .await?
-      .ok_or_else(|| format!("FA {:?} not found", asset_id))?;
-
+  for (asset_id, details) in asset_ids.into_iter().zip(details) {
+    let details =
+      details.ok_or_else(|| ImpexError::NotFound(format!("FA {:?} not found", asset_id)))?;
     fa_details.push((asset_id, details));
   }
 
@@ -348,13 +1536,14 @@ where
 }
 
 /// Fetch the nfa ids belonging to the game
-async fn fetch_nfa_ids<T>(
-  api: &OnlineClient<T>,
+async fn fetch_nfa_ids<B>(
+  api: &B,
   organization_id: &AccountId32,
-  block_hash: T::Hash,
+  block_hash: H256,
+  options: &FetchOptions,
 ) -> ResultOf<Vec<NonFungibleClassId>>
 where
-  T: subxt::Config,
+  B: ChainBackend + ?Sized,
 {
   let mut class_ids = vec![];
   // Iterate over the classAccounts storage to get all nfa of the game
@@ -367,55 +1556,55 @@ where
   // We can build a `StorageMapKey` that replicates that, and append those bytes to the above.
   StorageMapKey::new(organization_id, StorageHasher::Blake2_128Concat).to_bytes(&mut query_key);
   // Iterate over keys at that address and collect values to vec
-  let mut iter = AllKeyIter::new(api, query_key, block_hash, 10);
+  let mut iter = AllKeyIter::new(api, query_key, block_hash, options.page_size);
   while let Some(key) = iter.next().await? {
     // we need last 4 bytes (u32) - the asset id.
-    let class_id_encoded = key.0.as_slice()[key.0.len() - 4..].to_vec();
-    let class_id = NonFungibleClassId::decode(&mut &*class_id_encoded)?;
+    let class_id = NonFungibleClassId::decode(&mut key_suffix(&key.0, 4)?)?;
 
     class_ids.push(class_id);
   }
   Ok(class_ids)
 }
 
-async fn fetch_nfas<T>(
-  api: &OnlineClient<T>,
-  organization_id: &AccountId32,
-  block_hash: T::Hash,
+async fn fetch_nfas<B>(
+  api: &B,
+  class_ids: &[NonFungibleClassId],
+  block_hash: H256,
+  options: &FetchOptions,
 ) -> ResultOf<NonFungibleClassDetails>
 where
-  T: subxt::Config,
+  B: ChainBackend + ?Sized,
 {
-  // 1. Fetch the nfa ids belonging to the game
-  let class_ids = fetch_nfa_ids(api, organization_id, block_hash).await?;
-
-  // 2. Fetch details about each found assets
+  // Fetch details about each given class
+  let keys = class_ids
+    .iter()
+    .map(|class_id| {
+      finalbiome::storage()
+        .non_fungible_assets()
+        .classes(class_id)
+        .to_bytes()
+    })
+    .collect();
+  let details = fetch_values::<_, NonFungibleDetails>(api, keys, block_hash, options).await?;
   let mut nfa_details = vec![];
-  for class_id in class_ids {
-    let address = finalbiome::storage()
-      .non_fungible_assets()
-      .classes(class_id);
-    let details = api
-      .storage()
-      .fetch(&address, Some(block_hash))
-      .await?
-      .ok_or_else(|| format!("NFA {:?} not found", class_id))?;
-
-    nfa_details.push((class_id, details));
+  for (class_id, details) in class_ids.iter().zip(details) {
+    let details =
+      details.ok_or_else(|| ImpexError::NotFound(format!("NFA {:?} not found", class_id)))?;
+    nfa_details.push((*class_id, details));
   }
 
   Ok(nfa_details)
 }
 
 /// Fetch all attributes keys for given nfa
-async fn fetch_nfa_attributes_ids<T>(
-  api: &OnlineClient<T>,
-  _organization_id: &AccountId32,
-  block_hash: T::Hash,
+async fn fetch_nfa_attributes_ids<B>(
+  api: &B,
+  block_hash: H256,
   nfa_id: NonFungibleClassId,
+  options: &FetchOptions,
 ) -> ResultOf<Vec<AttributeKey>>
 where
-  T: subxt::Config,
+  B: ChainBackend + ?Sized,
 {
   let mut attrs_keys = vec![];
   // Iterate over the classAttributes storage to get all nfa attrs of the nfa
@@ -430,239 +1619,774 @@ where
   StorageMapKey::new(nfa_id, StorageHasher::Blake2_128Concat).to_bytes(&mut query_key);
   let partial_length = query_key.len();
   // Iterate over keys at that address and collect values to vec
-  let mut iter = AllKeyIter::new(api, query_key, block_hash, 10);
+  let mut iter = AllKeyIter::new(api, query_key, block_hash, options.page_size);
   while let Some(key) = iter.next().await? {
     // we need all bytes after `partial_length` + 16(Blake2_128Concat) - the attr id (key).
-    let attr_key_encoded = key.0.as_slice()[partial_length + 16..].to_vec();
-    let attr_key = AttributeKey::decode(&mut &*attr_key_encoded)?;
+    let attr_key = AttributeKey::decode(&mut key_after(&key.0, partial_length + 16)?)?;
 
     attrs_keys.push(attr_key);
   }
   Ok(attrs_keys)
 }
 
-async fn fetch_nfa_attributes<T>(
-  api: &OnlineClient<T>,
-  organization_id: &AccountId32,
-  block_hash: T::Hash,
+async fn fetch_nfa_attributes<B>(
+  api: &B,
+  class_ids: &[NonFungibleClassId],
+  block_hash: H256,
+  options: &FetchOptions,
 ) -> ResultOf<AttributesDetails>
 where
-  T: subxt::Config,
+  B: ChainBackend + ?Sized,
 {
-  // 1. Fetch the nfa ids belonging to the game
-  let class_ids = fetch_nfa_ids(api, organization_id, block_hash).await?;
-
-  // 2. Fetch all attributes for all nfas
+  // 1. Fetch the attribute keys of all given classes
+  let attr_keys: Vec<Vec<AttributeKey>> = stream::iter(class_ids)
+    .map(|class_id| fetch_nfa_attributes_ids(api, block_hash, *class_id, options))
+    .buffered(options.parallelism.max(1))
+    .try_collect()
+    .await?;
+  let attr_ids: Vec<(NonFungibleClassId, AttributeKey)> = class_ids
+    .iter()
+    .zip(attr_keys)
+    .flat_map(|(class_id, keys)| keys.into_iter().map(move |key| (*class_id, key)))
+    .collect();
+
+  // 2. Fetch values of all attributes
+  let keys = attr_ids
+    .iter()
+    .map(|(class_id, attr_key)| {
+      finalbiome::storage()
+        .non_fungible_assets()
+        .class_attributes(class_id, attr_key)
+        .to_bytes()
+    })
+    .collect();
+  let values = fetch_values(api, keys, block_hash, options).await?;
   let mut attributes = vec![];
-  for class_id in class_ids {
-    let attr_keys = fetch_nfa_attributes_ids(api, organization_id, block_hash, class_id).await?;
-    for attr_key in attr_keys {
-      let address = finalbiome::storage()
+  for ((class_id, attr_key), attr_value) in attr_ids.into_iter().zip(values) {
+    let attr_value = attr_value.ok_or_else(|| {
+      ImpexError::NotFound(format!(
+        "NFA Attr {:?} for NFA {:?} not found",
+        attr_key, class_id
+      ))
+    })?;
+    attributes.push((class_id, attr_key, attr_value));
+  }
+  Ok(attributes)
+}
+
+/// Fetch the minted instances of the given classes with their owners and attributes
+async fn fetch_nfa_instances<B>(
+  api: &B,
+  class_ids: &[NonFungibleClassId],
+  block_hash: H256,
+  options: &FetchOptions,
+) -> ResultOf<NonFungibleInstances>
+where
+  B: ChainBackend + ?Sized,
+{
+  // 1. Fetch the asset ids of all given classes
+  let asset_ids: Vec<Vec<NonFungibleAssetId>> = stream::iter(class_ids)
+    .map(|class_id| fetch_nfa_asset_ids(api, block_hash, *class_id, options))
+    .buffered(options.parallelism.max(1))
+    .try_collect()
+    .await?;
+  let class_assets: Vec<(NonFungibleClassId, NonFungibleAssetId)> = class_ids
+    .iter()
+    .zip(asset_ids)
+    .flat_map(|(class_id, ids)| ids.into_iter().map(move |id| (*class_id, id)))
+    .collect();
+
+  // 2. Fetch details and attribute keys of each asset
+  let keys = class_assets
+    .iter()
+    .map(|(class_id, asset_id)| {
+      finalbiome::storage()
+        .non_fungible_assets()
+        .assets(class_id, asset_id)
+        .to_bytes()
+    })
+    .collect();
+  let details = fetch_values::<_, NonFungibleAssetDetails>(api, keys, block_hash, options).await?;
+  let attr_keys: Vec<Vec<AttributeKey>> = stream::iter(&class_assets)
+    .map(|(_, asset_id)| fetch_nfa_asset_attributes_ids(api, block_hash, asset_id, options))
+    .buffered(options.parallelism.max(1))
+    .try_collect()
+    .await?;
+
+  // 3. Fetch values of all attributes
+  let attr_ids: Vec<(&NonFungibleAssetId, &AttributeKey)> = class_assets
+    .iter()
+    .zip(&attr_keys)
+    .flat_map(|((_, asset_id), keys)| keys.iter().map(move |key| (asset_id, key)))
+    .collect();
+  let keys = attr_ids
+    .iter()
+    .map(|(asset_id, attr_key)| {
+      finalbiome::storage()
         .non_fungible_assets()
-        .class_attributes(class_id, &attr_key);
-      let attr_value = api
-        .storage()
-        .fetch(&address, Some(block_hash))
-        .await?
-        .ok_or_else(|| format!("NFA Attr {:?} for NFA {:?} not found", attr_key, class_id))?;
-
-      attributes.push((class_id, attr_key, attr_value));
+        .attributes(*asset_id, *attr_key)
+        .to_bytes()
+    })
+    .collect();
+  let mut values = fetch_values(api, keys, block_hash, options)
+    .await?
+    .into_iter();
+
+  let mut instances = vec![];
+  for (((class_id, asset_id), details), keys) in
+    class_assets.into_iter().zip(details).zip(attr_keys)
+  {
+    let details = details.ok_or_else(|| {
+      ImpexError::NotFound(format!(
+        "NFA {:?} of class {:?} not found",
+        asset_id, class_id
+      ))
+    })?;
+    let mut attributes = vec![];
+    for key in keys {
+      let value = values.next().flatten().ok_or_else(|| {
+        ImpexError::NotFound(format!(
+          "NFA Attr {:?} for NFA {:?} not found",
+          key, asset_id
+        ))
+      })?;
+      attributes.push((key, value));
     }
+    instances.push(NonFungibleInstance {
+      class: class_id,
+      asset: asset_id,
+      owner: details.owner,
+      locked: !matches!(details.locked, runtime_types::pallet_support::Locker::None),
+      attributes,
+    });
   }
-  Ok(attributes)
+  Ok(instances)
 }
 
-/// Creates an appropriate game configuration in the network
-async fn post_to_node<T, P>(
-  api: &OnlineClient<T>,
-  game_spec: GameSpec,
-  organization_signer: PairSigner<T, P>,
-  manager_signer: PairSigner<T, P>,
-) -> ResultOf<()>
-where
-  T: subxt::Config<AccountId = AccountId32>,
-  P: sp_core::Pair,
-  <<T as subxt::Config>::ExtrinsicParams as subxt::tx::ExtrinsicParams<
-    <T as subxt::Config>::Index,
-    <T as subxt::Config>::Hash,
-  >>::OtherParams: std::default::Default,
-  <T as subxt::Config>::Address: std::convert::From<<T as subxt::Config>::AccountId>,
-  <T as subxt::Config>::Signature: std::convert::From<<P as sp_core::Pair>::Signature>,
-{
-  // todo: make transactional creation of the configuration in the network
-  let pr_steps_count = 1 + // create org
-    game_spec.organization_members.len() + 1 + // create members
-    game_spec.fa.len() + game_spec.nfa.len() + game_spec.attributes.len() +
-    game_spec.nfa.len() * 2 + // for each characteristic 
-    1 //onbording
-    ;
-
-  let pb = ProgressBar::new(pr_steps_count as u64);
-  pb.set_style(ProgressStyle::with_template("{spinner:.green} {wide_bar} {msg}").expect("ok"));
-
-  let org_name = game_spec.clone().organization_details.name.0;
-
-  // 1. Create organization
-  pb.set_message("Creating organization...");
-  let payload = finalbiome::tx()
-    .organization_identity()
-    .create_organization(org_name);
-
-  submit_default(api, &payload, &organization_signer).await?;
-  pb.inc(1);
-
-  // 2. Add members
-  // Also add the manager that is explicitly passed to the app (if the manager is not included in
-  // the specification)
-  pb.set_message("Adding members organization...");
-  let expl_manager = manager_signer.account_id().clone();
-  let mut members = game_spec.organization_members.clone();
-  if !members.contains(&expl_manager) {
-    members.push(expl_manager);
-  }
-  for member_id in members {
-    let payload = finalbiome::tx()
-      .organization_identity()
-      .add_member(member_id);
-
-    submit_default(api, &payload, &organization_signer).await?;
-    pb.inc(1);
-  }
-
-  // 3. Create FA
-  // for the each fa id in the game spec we store an id of the created asset.
-  pb.set_message("Creating FAs...");
-  // map stores the original and new id of the FA
-  let mut fa_ids_map = HashMap::new();
-
-  for (fa_id, fa_details) in game_spec.clone().fa {
-    let organization_id = MultiAddress::Id(organization_signer.account_id().clone());
-    let payload = finalbiome::tx().fungible_assets().create(
-      organization_id,
-      fa_details.name.0,
-      fa_details.top_upped,
-      fa_details.cup_global,
-      fa_details.cup_local,
-    );
+/// Fetch the ids of the minted assets of the given class
+async fn fetch_nfa_asset_ids<B>(
+  api: &B,
+  block_hash: H256,
+  class_id: NonFungibleClassId,
+  options: &FetchOptions,
+) -> ResultOf<Vec<NonFungibleAssetId>>
+where
+  B: ChainBackend + ?Sized,
+{
+  let mut asset_ids = vec![];
+  // Iterate over the assets storage to get all assets of the class
+  let key_addr = finalbiome::storage().non_fungible_assets().assets_root();
+  // Obtain the root bytes
+  let mut query_key = key_addr.to_root_bytes();
+  // We know that the first key is a NonFungibleClassId and is hashed by Blake2_128Concat.
+  // We can build a `StorageMapKey` that replicates that, and append those bytes to the above.
+  StorageMapKey::new(class_id, StorageHasher::Blake2_128Concat).to_bytes(&mut query_key);
+  // Iterate over keys at that address and collect values to vec
+  let mut iter = AllKeyIter::new(api, query_key, block_hash, options.page_size);
+  while let Some(key) = iter.next().await? {
+    // we need last 4 bytes (u32) - the asset id.
+    asset_ids.push(NonFungibleAssetId::decode(&mut key_suffix(&key.0, 4)?)?);
+  }
+  Ok(asset_ids)
+}
 
-    let fa_create = submit_default(api, &payload, &manager_signer).await?;
-    pb.inc(1);
+/// Fetch all attributes keys for given nfa instance
+async fn fetch_nfa_asset_attributes_ids<B>(
+  api: &B,
+  block_hash: H256,
+  asset_id: &NonFungibleAssetId,
+  options: &FetchOptions,
+) -> ResultOf<Vec<AttributeKey>>
+where
+  B: ChainBackend + ?Sized,
+{
+  let mut attrs_keys = vec![];
+  // Iterate over the attributes storage to get all attrs of the instance
+  let key_addr = finalbiome::storage()
+    .non_fungible_assets()
+    .attributes_root();
+  // Obtain the root bytes
+  let mut query_key = key_addr.to_root_bytes();
+  // We know that the first key is a NonFungibleAssetId and is hashed by Blake2_128Concat.
+  StorageMapKey::new(asset_id, StorageHasher::Blake2_128Concat).to_bytes(&mut query_key);
+  let partial_length = query_key.len();
+  // Iterate over keys at that address and collect values to vec
+  let mut iter = AllKeyIter::new(api, query_key, block_hash, options.page_size);
+  while let Some(key) = iter.next().await? {
+    // we need all bytes after `partial_length` + 16(Blake2_128Concat) - the attr id (key).
+    attrs_keys.push(AttributeKey::decode(&mut key_after(
+      &key.0,
+      partial_length + 16,
+    )?)?);
+  }
+  Ok(attrs_keys)
+}
 
-    // lookup events and find asset id of the created asset
-    let created_event = fa_create
-      .find_first::<finalbiome::fungible_assets::events::Created>()?
-      .ok_or_else(|| format!("Creating of FA {:?} failed", fa_id))?;
-
-    fa_ids_map.insert(fa_id, created_event.asset_id);
-  }
-
-  // 4. Create NFA
-  // for the each fa id in the game spec we store an id of the created asset.
-  pb.set_message("Creating NFAs...");
-  // map stores the original and new id of the FA
-  let mut nfa_ids_map = HashMap::new();
-
-  for (nfa_id_orig, nfa_details) in game_spec.clone().nfa {
-    let organization_id = MultiAddress::Id(organization_signer.account_id().clone());
-    // 1. Creata nfa
-    let payload = finalbiome::tx()
-      .non_fungible_assets()
-      .create(organization_id.clone(), nfa_details.name.0);
-    let nfa_create = submit_default(api, &payload, &manager_signer).await?;
-    pb.inc(1);
-    // lookup events and find asset id of the created asset
-    let created_event = nfa_create
-      .find_first::<finalbiome::non_fungible_assets::events::Created>()?
-      .ok_or_else(|| format!("Creating of NFA {:?} failed", nfa_id_orig))?;
-    let nfa_id_created = created_event.class_id;
-    nfa_ids_map.insert(nfa_id_orig, nfa_id_created);
-
-    // 2. Add attributes
-    let attrs = game_spec
-      .attributes
-      .clone()
+/// Fetch the mechanics in progress started by the given users of the organization
+async fn fetch_mechanics<B>(
+  api: &B,
+  organization_id: &AccountId32,
+  users: &[AccountId32],
+  block_hash: H256,
+  options: &FetchOptions,
+) -> ResultOf<Vec<MechanicState>>
+where
+  B: ChainBackend + ?Sized,
+{
+  // 1. Fetch the storage keys of the mechanics of each user
+  let user_keys: Vec<Vec<Vec<u8>>> = stream::iter(users)
+    .map(|user| async move {
+      let gamer = runtime_types::pallet_support::GamerAccount {
+        account_id: user.clone(),
+        organization_id: organization_id.clone(),
+      };
+      let key_addr = finalbiome::storage().mechanics().mechanics_root();
+      let mut query_key = key_addr.to_root_bytes();
+      // The first key is a GamerAccount and is hashed by Twox64Concat.
+      StorageMapKey::new(&gamer, StorageHasher::Twox64Concat).to_bytes(&mut query_key);
+      let mut keys = vec![];
+      let mut iter = AllKeyIter::new(api, query_key, block_hash, options.page_size);
+      while let Some(key) = iter.next().await? {
+        keys.push(key.0);
+      }
+      ResultOf::Ok(keys)
+    })
+    .buffered(options.parallelism.max(1))
+    .try_collect()
+    .await?;
+  let user_keys: Vec<(&AccountId32, Vec<u8>)> = users
+    .iter()
+    .zip(user_keys)
+    .flat_map(|(user, keys)| keys.into_iter().map(move |key| (user, key)))
+    .collect();
+
+  // 2. Fetch details of the mechanics
+  let keys = user_keys.iter().map(|(_, key)| key.clone()).collect();
+  let details: Vec<
+    Option<runtime_types::pallet_mechanics::types::MechanicDetails<AccountId32, u32>>,
+  > = fetch_values(api, keys, block_hash, options).await?;
+  let mut mechanics = vec![];
+  for ((user, key), details) in user_keys.into_iter().zip(details) {
+    // we need last 4 bytes (u32) - the mechanic id.
+    let id = u32::decode(&mut key_suffix(&key, 4)?)?;
+    let details = details
+      .ok_or_else(|| ImpexError::NotFound(format!("Mechanic {} of {} not found", id, user)))?;
+    let data = match details.data {
+      runtime_types::pallet_mechanics::types::MechanicData::BuyNfa => MechanicKind::BuyNfa,
+      runtime_types::pallet_mechanics::types::MechanicData::Bet(bet) => MechanicKind::Bet {
+        outcomes: bet.outcomes.0,
+      },
+    };
+    let locked = details
+      .locked
+      .0
       .into_iter()
-      .filter(|(class, ..)| class == &nfa_id_orig);
-    for (_class_id, key, value) in attrs {
-      let attr = Attribute { key, value };
-      let payload = finalbiome::tx().non_fungible_assets().create_attribute(
-        organization_id.clone(),
-        nfa_id_created,
-        attr,
-      );
-      submit_default(api, &payload, &manager_signer).await?;
-      pb.inc(1);
-    }
+      .map(|asset| match asset {
+        runtime_types::pallet_support::LockedAccet::Fa(fa, amount) => LockedAsset::Fa(fa, amount),
+        runtime_types::pallet_support::LockedAccet::Nfa(class, asset) => {
+          LockedAsset::Nfa(class, asset)
+        },
+      })
+      .collect();
+    mechanics.push(MechanicState {
+      gamer: user.clone(),
+      id,
+      timeout: details.timeout_id,
+      data,
+      locked,
+    });
+  }
+  Ok(mechanics)
+}
 
-    // 3. Set characteristics
-    // 3.1 Bettor
-    let nfa_spec = game_spec.clone().get_nfa(nfa_id_orig);
-    if let Some(mut bettor) = nfa_spec.bettor {
-      // remap orig assets ids into created
-      for winning in &mut bettor.winnings.0 {
-        match winning {
-          BettorWinning::Fa(id, _) => *id = *fa_ids_map.get(id).expect("no fa found"),
-          BettorWinning::Nfa(id) => *id = *nfa_ids_map.get(id).expect("no fa found"),
-        }
+/// Fetch the top-upped FA among the given ones and the accounts waiting for their top-up
+async fn fetch_top_ups<B>(
+  api: &B,
+  fa_ids: &[FungibleAssetId],
+  block_hash: H256,
+  options: &FetchOptions,
+) -> ResultOf<(Vec<FungibleAssetId>, Vec<TopUpState>)>
+where
+  B: ChainBackend + ?Sized,
+{
+  // 1. Fetch the top-upped FA of the game
+  let address = finalbiome::storage().fungible_assets().top_upped_assets();
+  let top_upped: Vec<FungibleAssetId> = fetch_value::<
+    _,
+    runtime_types::sp_runtime::bounded::weak_bounded_vec::WeakBoundedVec<_>,
+  >(api, address.to_bytes(), block_hash)
+  .await?
+  .map(|assets| assets.0)
+  .unwrap_or_default()
+  .into_iter()
+  .filter(|fa_id| fa_ids.contains(fa_id))
+  .collect();
+
+  // 2. Fetch the accounts in the queue of each FA
+  let queued: Vec<Vec<AccountId32>> = stream::iter(fa_ids)
+    .map(|fa_id| async move {
+      let key_addr = finalbiome::storage().fungible_assets().top_up_queue_root();
+      let mut query_key = key_addr.to_root_bytes();
+      // The first key is a FungibleAssetId and is hashed by Blake2_128Concat.
+      StorageMapKey::new(fa_id, StorageHasher::Blake2_128Concat).to_bytes(&mut query_key);
+      let mut accounts = vec![];
+      let mut iter = AllKeyIter::new(api, query_key, block_hash, options.page_size);
+      while let Some(key) = iter.next().await? {
+        // we need last 32 bytes - the account id.
+        accounts.push(account_from_key(&key.0)?);
       }
-      let characteristic = Characteristic::Bettor(Some(bettor));
-      let payload = finalbiome::tx().non_fungible_assets().set_characteristic(
-        organization_id.clone(),
-        nfa_id_created,
-        characteristic,
-      );
-      submit_default(api, &payload, &manager_signer).await?;
+      ResultOf::Ok(accounts)
+    })
+    .buffered(options.parallelism.max(1))
+    .try_collect()
+    .await?;
+  let queued: Vec<(FungibleAssetId, AccountId32)> = fa_ids
+    .iter()
+    .zip(queued)
+    .flat_map(|(fa_id, accounts)| accounts.into_iter().map(move |account| (*fa_id, account)))
+    .collect();
+
+  // 3. Fetch the current balances of the queued accounts
+  let keys = queued
+    .iter()
+    .map(|(fa_id, account)| {
+      finalbiome::storage()
+        .fungible_assets()
+        .accounts(account, fa_id)
+        .to_bytes()
+    })
+    .collect();
+  let asset_accounts: Vec<Option<runtime_types::pallet_fungible_assets::types::AssetAccount>> =
+    fetch_values(api, keys, block_hash, options).await?;
+  let top_up_queue = queued
+    .into_iter()
+    .zip(asset_accounts)
+    .map(|((fa, account), asset_account)| TopUpState {
+      fa,
+      account,
+      balance: asset_account.map_or(
+        runtime_types::pallet_support::types::fungible_asset_balance::FungibleAssetBalance(0),
+        |a| a.balance,
+      ),
+    })
+    .collect();
+  Ok((top_upped, top_up_queue))
+}
+
+/// Creates an appropriate game configuration in the network.
+///
+/// Calls of the same signer are submitted one after another with the locally tracked nonces
+/// without waiting for their inclusion, up to the window of the options. The calls in flight are
+/// awaited when the signer changes or when the next call needs the ids of the assets they create.
+/// If the runtime has `Utility::batch_all`, the neighbouring batchable calls of the signer are
+/// grouped into batch extrinsics.
+#[allow(clippy::too_many_arguments)]
+async fn post_to_node<B>(
+  api: &B,
+  plan: &ImportPlan,
+  organization_signer: Option<sr25519::Pair>,
+  manager_signer: sr25519::Pair,
+  player_signers: HashMap<usize, sr25519::Pair>,
+  journal: &mut ImportJournal,
+  journal_path: Option<&Path>,
+  options: &SubmitOptions,
+) -> ResultOf<ImportReport>
+where
+  B: ChainBackend + ?Sized,
+{
+  // todo: make transactional creation of the configuration in the network, only the calls of
+  // a batch are dispatched atomically
+  let pb = ProgressBar::new(plan.steps.len() as u64);
+  pb.set_style(
+    ProgressStyle::with_template("{spinner:.green} {wide_bar} {msg}")
+      .unwrap_or_else(|_| ProgressStyle::default_bar()),
+  );
+  // skip the steps completed by the previous run
+  pb.set_position((journal.completed_steps + journal.completed_ahead.len()) as u64);
+
+  let organization_account = plan.organization.clone();
+  let window = options.window.max(1);
+  let mut progress = ImportProgress {
+    plan,
+    // maps store the original and new ids of the assets
+    fa_ids_map: journal.fa_ids_map(),
+    nfa_ids_map: journal.nfa_ids_map(),
+    journal,
+    journal_path,
+    pb,
+  };
+  // next nonces of the signers, fetched on the first call of the signer
+  let nonces: Mutex<HashMap<AccountId32, u32>> = Mutex::new(HashMap::new());
+  let mut in_flight = FuturesOrdered::new();
+  let mut in_flight_signer: Option<AccountId32> = None;
+
+  // independent calls of the signer are dispatched in batches if the runtime supports them
+  let batch_size = match options.batch_size > 1 && api.supports_batch().await? {
+    true => options.batch_size,
+    false => 1,
+  };
+
+  let mut index = progress.journal.completed_steps;
+  while index < plan.steps.len() {
+    if progress.journal.completed_ahead.contains(&index) {
+      progress.wait(&mut in_flight, 0).await?;
+      progress.complete_ahead(index)?;
+      index += 1;
+      continue;
     }
-    pb.inc(1);
-    // 3.2 Purchased
-    if let Some(mut purchased) = nfa_spec.purchased {
-      // remap orig assets ids into created
-      for offer in &mut purchased.offers.0 {
-        offer.fa = *fa_ids_map.get(&offer.fa).expect("no fa found");
+    let step = &plan.steps[index];
+    let signer = match step.signer {
+      StepSigner::Organization => organization_signer.as_ref().ok_or_else(|| {
+        ImpexError::InvalidArgument("Signer of the organization is not set".to_string())
+      })?,
+      StepSigner::Manager => &manager_signer,
+      StepSigner::Player(n) => player_signers.get(&n).ok_or_else(|| {
+        ImpexError::InvalidArgument(format!("Signer of the player {} is not set", n))
+      })?,
+    };
+    let account: AccountId32 = signer.public().into();
+    // calls of another signer may depend on the calls in flight
+    if in_flight_signer.as_ref() != Some(&account) {
+      progress.wait(&mut in_flight, 0).await?;
+    }
+    let count = match step.call.batchable() {
+      true => plan.steps[index..]
+        .iter()
+        .zip(index..)
+        .take(batch_size)
+        .take_while(|(next, n)| {
+          next.signer == step.signer
+            && next.call.batchable()
+            && !progress.journal.completed_ahead.contains(n)
+        })
+        .count(),
+      false => 1,
+    };
+    let steps = index..index + count;
+    let calls = match progress.runtime_calls(steps.clone(), &organization_account) {
+      Ok(calls) => calls,
+      // the assets of the calls are created by the calls in flight
+      Err(_) if !in_flight.is_empty() => {
+        progress.wait(&mut in_flight, 0).await?;
+        progress.runtime_calls(steps.clone(), &organization_account)?
+      },
+      Err(e) => return Err(e),
+    };
+    progress.wait(&mut in_flight, window - 1).await?;
+
+    let nonce = match next_nonce(&nonces, &account) {
+      Some(nonce) => nonce,
+      None => api.account_nonce(&account).await?,
+    };
+    set_next_nonce(&nonces, account.clone(), nonce + 1);
+    progress.pb.set_message(step.call.name());
+    index = steps.end;
+    in_flight.push_back(async move {
+      let result = submit_with_retry(api, signer, calls, nonce, options).await;
+      (steps, result)
+    });
+    in_flight_signer = Some(account);
+  }
+  progress.wait(&mut in_flight, 0).await?;
+
+  progress.pb.finish_with_message("done");
+  Ok(ImportReport::new(organization_account, progress.journal))
+}
+
+/// Submits the calls in one extrinsic, the transient failures are retried with the backoff of
+/// the options.
+///
+/// The transaction is submitted again with the same nonce, so the calls in flight after it stay
+/// valid. If the nonce turns out to be used, it's not retried: the nonce may be used by a failed
+/// attempt which was included after all, and a new nonce could collide with the calls in flight.
+/// Before an attempt which timed out is submitted again, the nonce of the signer is checked, and
+/// if the attempt may still be included, the calls are reported as [`ImpexError::Unconfirmed`].
+async fn submit_with_retry<B>(
+  api: &B,
+  signer: &sr25519::Pair,
+  calls: Vec<RuntimeCall>,
+  nonce: u32,
+  options: &SubmitOptions,
+) -> ResultOf<Vec<SubmittedCall>>
+where
+  B: ChainBackend + ?Sized,
+{
+  let mut retries = 0;
+  let mut timed_out = false;
+  loop {
+    let submission = async {
+      match <[RuntimeCall; 1]>::try_from(calls.clone()) {
+        Ok([call]) => api.submit(signer, call, Some(nonce)).await.map(|s| vec![s]),
+        Err(calls) => api.submit_batch(signer, calls, Some(nonce)).await,
+      }
+    };
+    let e = match tokio::time::timeout(options.timeout, submission).await {
+      Ok(Ok(submitted)) => return Ok(submitted),
+      Ok(Err(e)) => e,
+      Err(_) => ImpexError::Timeout(options.timeout),
+    };
+    let stale = matches!(e, ImpexError::Rejected(Rejection::Stale));
+    if matches!(e, ImpexError::Timeout(_)) || (stale && timed_out) {
+      timed_out = true;
+      // the next nonce of the account counts the transactions in the pool as well
+      let account: AccountId32 = signer.public().into();
+      if api.account_nonce(&account).await? > nonce {
+        return Err(ImpexError::Unconfirmed(Box::new(e)));
       }
+    }
+    if !e.is_retryable() || retries >= options.retries || stale {
+      return Err(e);
+    }
+    tokio::time::sleep(options.backoff * 2u32.saturating_pow(retries)).await;
+    retries += 1;
+  }
+}
+
+/// Returns the next nonce of the account tracked by the import
+fn next_nonce(nonces: &Mutex<HashMap<AccountId32, u32>>, account: &AccountId32) -> Option<u32> {
+  // the nonces are never left half written, so a poisoned lock is still usable
+  let nonces = nonces.lock().unwrap_or_else(|e| e.into_inner());
+  nonces.get(account).copied()
+}
+
+fn set_next_nonce(nonces: &Mutex<HashMap<AccountId32, u32>>, account: AccountId32, nonce: u32) {
+  let mut nonces = nonces.lock().unwrap_or_else(|e| e.into_inner());
+  nonces.insert(account, nonce);
+}
+
+/// Submitted import with the ids of the created assets
+struct ImportProgress<'a> {
+  plan: &'a ImportPlan,
+  journal: &'a mut ImportJournal,
+  journal_path: Option<&'a Path>,
+  pb: ProgressBar,
+  fa_ids_map: HashMap<FungibleAssetId, FungibleAssetId>,
+  nfa_ids_map: HashMap<NonFungibleClassId, NonFungibleClassId>,
+}
 
-      let characteristic = Characteristic::Purchased(Some(purchased));
-      let payload = finalbiome::tx().non_fungible_assets().set_characteristic(
-        organization_id.clone(),
-        nfa_id_created,
+impl ImportProgress<'_> {
+  /// Builds the calls of the steps with the ids of the created assets
+  fn runtime_calls(
+    &self,
+    steps: Range<usize>,
+    organization: &AccountId32,
+  ) -> ResultOf<Vec<RuntimeCall>> {
+    self.plan.steps[steps]
+      .iter()
+      .map(|step| self.runtime_call(&step.call, organization))
+      .collect()
+  }
+
+  /// Builds the call of the step with the ids of the created assets
+  fn runtime_call(&self, call: &ImportCall, organization: &AccountId32) -> ResultOf<RuntimeCall> {
+    use runtime_types::{
+      pallet_fungible_assets::pallet::Call as FaCall,
+      pallet_non_fungible_assets::pallet::Call as NfaCall,
+      pallet_organization_identity::pallet::Call as OrganizationCall,
+    };
+
+    let (fa_ids_map, nfa_ids_map) = (&self.fa_ids_map, &self.nfa_ids_map);
+    let organization_id = MultiAddress::Id(organization.clone());
+    let created_nfa = |class| {
+      nfa_ids_map
+        .get(&class)
+        .copied()
+        .ok_or_else(|| ImpexError::NotFound(format!("NFA {:?} is not created", class)))
+    };
+
+    let call = match call.clone() {
+      ImportCall::CreateOrganization { name } => {
+        RuntimeCall::OrganizationIdentity(OrganizationCall::create_organization { name: name.0 })
+      },
+      ImportCall::AddMember { who } => {
+        RuntimeCall::OrganizationIdentity(OrganizationCall::add_member { who })
+      },
+      ImportCall::CreateFa {
+        name,
+        top_upped,
+        cup_global,
+        cup_local,
+        ..
+      } => RuntimeCall::FungibleAssets(FaCall::create {
+        organization_id: organization_id.clone(),
+        name: name.0,
+        top_upped,
+        cup_global,
+        cup_local,
+      }),
+      ImportCall::CreateNfa { name, .. } => RuntimeCall::NonFungibleAssets(NfaCall::create {
+        organization_id: organization_id.clone(),
+        name: name.0,
+      }),
+      ImportCall::CreateAttribute { class, attribute } => {
+        RuntimeCall::NonFungibleAssets(NfaCall::create_attribute {
+          organization_id: organization_id.clone(),
+          class_id: created_nfa(class)?,
+          attribute,
+        })
+      },
+      ImportCall::SetCharacteristic {
+        class,
         characteristic,
-      );
-      submit_default(api, &payload, &manager_signer).await?;
-    }
-    pb.inc(1);
+      } => {
+        // remap orig assets ids into created
+        let characteristic = remap_characteristic(characteristic, fa_ids_map, nfa_ids_map)
+          .ok_or_else(|| {
+            ImpexError::NotFound(format!(
+              "Assets of the characteristic of NFA {:?} not found",
+              class
+            ))
+          })?;
+        RuntimeCall::NonFungibleAssets(NfaCall::set_characteristic {
+          organization_id: organization_id.clone(),
+          class_id: created_nfa(class)?,
+          characteristic,
+        })
+      },
+      ImportCall::SetOnboardingAssets { assets } => {
+        // transform orig ids to created
+        let air_assets = remap_onboarding_assets(assets, fa_ids_map, nfa_ids_map)
+          .ok_or_else(|| ImpexError::NotFound("Onboarding assets not found".to_string()))?;
+        RuntimeCall::OrganizationIdentity(OrganizationCall::set_onboarding_assets {
+          organization_id: organization.clone(),
+          // an empty list clears the onboarding assets
+          assets: (!air_assets.is_empty()).then_some(BoundedVec(air_assets)),
+        })
+      },
+      ImportCall::RemoveMember { who } => {
+        RuntimeCall::OrganizationIdentity(OrganizationCall::remove_member { who })
+      },
+      ImportCall::RemoveAttribute { class, key } => {
+        RuntimeCall::NonFungibleAssets(NfaCall::remove_attribute {
+          organization_id: organization_id.clone(),
+          class_id: created_nfa(class)?,
+          attribute_name: key,
+        })
+      },
+      ImportCall::DestroyFa { asset_id } => RuntimeCall::FungibleAssets(FaCall::destroy {
+        organization_id: organization_id.clone(),
+        asset_id,
+      }),
+      ImportCall::DestroyNfa { class_id } => RuntimeCall::NonFungibleAssets(NfaCall::destroy {
+        organization_id: organization_id.clone(),
+        class_id,
+      }),
+      ImportCall::Onboarding { .. } => {
+        RuntimeCall::OrganizationIdentity(OrganizationCall::onboarding {
+          organization_id: organization.clone(),
+        })
+      },
+    };
+    Ok(call)
   }
 
-  // LAST. Create Onboarding
-  if let Some(onboarding) = game_spec.organization_details.onboarding_assets {
-    let mut air_assets = vec![];
-    // transform orig ids to created
-    for asset_orig in onboarding.0 {
-      let asset_created = match asset_orig {
-        AirDropAsset::Fa(id, bal) => {
-          AirDropAsset::Fa(*fa_ids_map.get(&id).expect("fa not found"), bal)
-        },
-        AirDropAsset::Nfa(id, attrs) => {
-          AirDropAsset::Nfa(*nfa_ids_map.get(&id).expect("fa not found"), attrs)
-        },
+  /// Waits until no more than `limit` extrinsics are in flight and records their results in the
+  /// order of the steps.
+  ///
+  /// If an extrinsic fails, the remaining extrinsics in flight are still awaited and their
+  /// completed steps are recorded as completed ahead, then the error of the failed one is
+  /// returned.
+  async fn wait<F>(&mut self, in_flight: &mut FuturesOrdered<F>, limit: usize) -> ResultOf<()>
+  where
+    F: Future<Output = (Range<usize>, ResultOf<Vec<SubmittedCall>>)>,
+  {
+    while in_flight.len() > limit {
+      let (steps, result) = match in_flight.next().await {
+        Some(completed) => completed,
+        None => break,
       };
+      if let Err(e) = result.and_then(|submitted| self.record_all(steps.clone(), &submitted)) {
+        if matches!(e, ImpexError::Unconfirmed(_)) {
+          self.journal.unconfirmed.extend(steps);
+        }
+        while let Some((steps, result)) = in_flight.next().await {
+          match result.and_then(|submitted| self.record_all(steps.clone(), &submitted)) {
+            Ok(()) => self.journal.completed_ahead.extend(steps),
+            Err(ImpexError::Unconfirmed(_)) => self.journal.unconfirmed.extend(steps),
+            Err(_) => (),
+          }
+        }
+        self.save()?;
+        return Err(e);
+      }
+      self.journal.completed_steps += steps.len();
+      self.save()?;
+    }
+    Ok(())
+  }
 
-      air_assets.push(asset_created);
+  /// Records the results of the steps submitted in one extrinsic
+  fn record_all(&mut self, steps: Range<usize>, submitted: &[SubmittedCall]) -> ResultOf<()> {
+    if submitted.len() != steps.len() {
+      return Err(ImpexError::Decode(format!(
+        "Expected the results of {} calls, got {}",
+        steps.len(),
+        submitted.len()
+      )));
+    }
+    for (index, submitted) in steps.zip(submitted) {
+      self.record(index, submitted)?;
     }
+    Ok(())
+  }
 
-    let payload = finalbiome::tx()
-      .organization_identity()
-      .set_onboarding_assets(
-        organization_signer.account_id().clone(),
-        Some(runtime_types::sp_runtime::bounded::bounded_vec::BoundedVec(
-          air_assets,
-        )),
-      );
-    submit_default(api, &payload, &manager_signer).await?;
-    pb.inc(1);
+  /// Marks the step completed by the previous run after the failed step
+  fn complete_ahead(&mut self, index: usize) -> ResultOf<()> {
+    self.journal.completed_ahead.retain(|step| *step != index);
+    self.journal.completed_steps += 1;
+    self.save()
   }
 
-  pb.finish_with_message("done");
-  Ok(())
+  /// Records the ids of the created asset and the result of the step
+  fn record(&mut self, index: usize, submitted: &SubmittedCall) -> ResultOf<()> {
+    use runtime_types::{
+      pallet_fungible_assets::pallet::Event as FaEvent,
+      pallet_non_fungible_assets::pallet::Event as NfaEvent,
+    };
+
+    let step = &self.plan.steps[index];
+    // lookup events and find asset id of the created asset
+    match step.call {
+      ImportCall::CreateFa { fa, .. } => {
+        let asset_id = submitted
+          .events
+          .iter()
+          .find_map(|event| match event {
+            RuntimeEvent::FungibleAssets(FaEvent::Created { asset_id, .. }) => Some(*asset_id),
+            _ => None,
+          })
+          .ok_or_else(|| ImpexError::NotFound(format!("Creating of FA {:?} failed", fa)))?;
+        self.fa_ids_map.insert(fa, asset_id);
+        self.journal.fa_ids_map.push((fa, asset_id));
+      },
+      ImportCall::CreateNfa { class, .. } => {
+        let class_id = submitted
+          .events
+          .iter()
+          .find_map(|event| match event {
+            RuntimeEvent::NonFungibleAssets(NfaEvent::Created { class_id, .. }) => Some(*class_id),
+            _ => None,
+          })
+          .ok_or_else(|| ImpexError::NotFound(format!("Creating of NFA {:?} failed", class)))?;
+        self.nfa_ids_map.insert(class, class_id);
+        self.journal.nfa_ids_map.push((class, class_id));
+      },
+      _ => {},
+    }
+    self
+      .journal
+      .steps
+      .push(StepReport::new(step.call.name(), submitted));
+    self.pb.inc(1);
+    Ok(())
+  }
+
+  fn save(&self) -> ResultOf<()> {
+    match self.journal_path {
+      Some(journal_path) => self.journal.save(journal_path),
+      None => Ok(()),
+    }
+  }
 }
 
 /// Transform uri str to Public key
@@ -674,7 +2398,9 @@ where
   if let Ok((public_key, _network)) = Pair::Public::from_string_with_version(uri) {
     Ok(public_key)
   } else {
-    Err("Invalid organization address/URI given".into())
+    Err(ImpexError::InvalidArgument(
+      "Invalid organization address/URI given".to_string(),
+    ))
   }
 }
 
@@ -683,7 +2409,7 @@ where
 /// 1. Try to construct the `Pair` while using `uri` as input for [`sp_core::Pair::from_phrase`].
 ///
 /// 2. Try to construct the `Pair` while using `uri` as input for
-/// [`sp_core::Pair::from_string_with_seed`].
+///    [`sp_core::Pair::from_string_with_seed`].
 fn pair_from_suri<Pair>(suri: &str, password: Option<SecretString>) -> ResultOf<Pair>
 where
   Pair: sp_core::Pair,
@@ -696,18 +2422,48 @@ where
   } else if let Ok((pair, _seed)) = Pair::from_string_with_seed(suri, password) {
     Ok(pair)
   } else {
-    Err("Invalid phrase/URI given for the organization seed".into())
+    Err(ImpexError::InvalidArgument(
+      "Invalid phrase/URI given for the organization seed".to_string(),
+    ))
   }
 }
 
-/// Load the game spec form file by given path
+/// Load the game spec form file by given path.
+///
+/// Specs stored in the older formats are upgraded to the current one.
 fn load_game_spec<P>(path: P) -> ResultOf<GameSpec>
 where
   P: AsRef<Path>,
 {
-  // check file exists
-  let f = File::open(&path)?;
-  let reader = BufReader::new(f);
-  let game_spec: GameSpec = serde_json::from_reader(reader)?;
+  let (game_spec, _version) = read_game_spec_file(path)?;
   Ok(game_spec)
 }
+
+/// Read the game spec file and return the spec with the format version it was stored in.
+///
+/// The format of the file is detected by its extension.
+fn read_game_spec_file<P>(path: P) -> ResultOf<(GameSpec, u32)>
+where
+  P: AsRef<Path>,
+{
+  let content = fs::read_to_string(&path).map_err(|e| ImpexError::file(path.as_ref(), e))?;
+  read_game_spec(&content, SpecFormat::from_path(&path)).map_err(|e| {
+    ImpexError::Spec(format!(
+      "Cannot read game spec {}: {}",
+      path.as_ref().display(),
+      e
+    ))
+  })
+}
+
+/// Save the game spec to file by given path in the given format.
+///
+/// Assets are written with the handles generated from their names instead of the ids.
+fn save_game_spec<P>(path: P, game_spec: &GameSpec, format: SpecFormat) -> ResultOf<()>
+where
+  P: AsRef<Path>,
+{
+  let content = write_game_spec(game_spec, format)?;
+  fs::write(&path, content).map_err(|e| ImpexError::file(path.as_ref(), e))?;
+  Ok(())
+}
@@ -1,5 +1,5 @@
 use clap::Parser;
-use std::path::PathBuf;
+use std::{io::Write, path::PathBuf, time::Duration};
 
 #[derive(Parser)]
 #[clap(
@@ -23,6 +23,31 @@ enum Impex {
     /// Whether to overwrite the file if it exists?
     #[clap(long, short = 'w', default_value = "false")]
     overwrite: bool,
+    /// Format of the game file: json, json-pretty, yaml or toml.
+    /// [default: detected by the extension of <GAME_SPEC>, json if unknown]
+    #[clap(long, short = 'f')]
+    format: Option<finalbiome_impex::SpecFormat>,
+    /// Block number or 0x-prefixed block hash at which the game is exported. [default: the best block]
+    #[clap(long)]
+    at: Option<finalbiome_impex::BlockAt>,
+    /// Export at the latest finalized block instead of the best one.
+    #[clap(long, conflicts_with = "at")]
+    finalized: bool,
+    /// Number of storage keys requested from the node at once.
+    #[clap(long, default_value = "100")]
+    page_size: u32,
+    /// Number of requests sent to the node concurrently.
+    #[clap(long, default_value = "8")]
+    parallelism: usize,
+    /// Export the users (players) of the game.
+    #[clap(long)]
+    include_users: bool,
+    /// Export the FA balances of the users of the game.
+    #[clap(long)]
+    include_balances: bool,
+    /// Export the minted NFA instances with their owners and attributes.
+    #[clap(long)]
+    include_instances: bool,
   },
   /// Create game from game spec file.
   Import {
@@ -38,27 +63,671 @@ enum Impex {
     /// Game manager on whose behalf it will be configured. May be a secret seed or secret URI.
     #[clap(long, short = 'm', required = true)]
     manager_seed: String,
+    /// Path to the journal file in which the progress of the import is recorded.
+    /// [default: <GAME_SPEC>.journal.json]
+    #[clap(long, short = 'j', conflicts_with = "resume")]
+    journal: Option<PathBuf>,
+    /// Continue the failed import recorded in the given journal file.
+    #[clap(long, short = 'r')]
+    resume: Option<PathBuf>,
+    /// Path to the file to which the ids of the created assets and the results of the submitted
+    /// calls will be written.
+    #[clap(long, conflicts_with = "dry_run")]
+    report: Option<PathBuf>,
+    /// Print the calls which would be submitted without submitting anything.
+    #[clap(long, default_value = "false")]
+    dry_run: bool,
+    /// Print the dry run plan as JSON.
+    #[clap(long, default_value = "false", requires = "dry_run")]
+    json: bool,
+    /// Build the dry run plan without connecting to the node, as if the organization does not
+    /// exist in the network.
+    #[clap(long, default_value = "false", requires = "dry_run")]
+    offline: bool,
+    /// Seed template of the player accounts with the `n` placeholder in braces, which is replaced
+    /// with the number of the player. A player is onboarded to the game for each user of the spec.
+    #[clap(long, short = 'p')]
+    players: Option<String>,
+    /// Number of calls of the same signer submitted without waiting for their inclusion.
+    #[clap(long, default_value = "16")]
+    window: usize,
+    /// Max number of calls grouped into one Utility::batch_all extrinsic if the runtime has it.
+    /// 1 disables batching.
+    #[clap(long, default_value = "32")]
+    batch_size: usize,
+    /// Number of times a transaction is submitted again after a transient failure, e.g. a
+    /// connection drop or a rejection by the transaction pool.
+    #[clap(long, default_value = "3")]
+    retries: u32,
+    /// Delay in milliseconds before the first retry, doubled for each next one.
+    #[clap(long, default_value = "2000")]
+    backoff: u64,
+    /// Seconds in which a transaction must be included in a block, otherwise it is retried.
+    #[clap(long, default_value = "60")]
+    timeout: u64,
+    /// Tear down the items created by the import if it fails, instead of keeping them for
+    /// resume.
+    #[clap(long, default_value = "false", conflicts_with = "dry_run")]
+    teardown_on_failure: bool,
+    /// Import the game without the FA balances of the spec. The runtime has no call to issue or
+    /// transfer FA, so the import of a spec with balances fails without this flag.
+    #[clap(long, default_value = "false")]
+    skip_balances: bool,
+    /// Import the game without the minted NFA instances of the spec. The runtime has no call to
+    /// mint NFA, so the import of a spec with instances fails without this flag.
+    #[clap(long, default_value = "false")]
+    skip_instances: bool,
+  },
+  /// Update existing game to the state of the game spec file.
+  Apply {
+    /// RPC endpoint of the network node.
+    #[clap(long, short, default_value = "ws://127.0.0.1:9944")]
+    endpoint: String,
+    /// Path to the game file from which the game configuration will be read.
+    #[clap(long, short, required = true)]
+    game_spec: PathBuf,
+    /// Game organization account key seed. May be a secret seed or secret URI.
+    #[clap(long, short = 's', required = true)]
+    organization_seed: String,
+    /// Game manager on whose behalf it will be configured. May be a secret seed or secret URI.
+    #[clap(long, short = 'm', required = true)]
+    manager_seed: String,
+    /// Path to the file to which the ids of the assets and the results of the submitted calls
+    /// will be written.
+    #[clap(long, conflicts_with = "dry_run")]
+    report: Option<PathBuf>,
+    /// Print the calls which would be submitted without submitting anything.
+    #[clap(long, default_value = "false")]
+    dry_run: bool,
+    /// Print the dry run plan as JSON.
+    #[clap(long, default_value = "false", requires = "dry_run")]
+    json: bool,
+    /// Number of calls of the same signer submitted without waiting for their inclusion.
+    #[clap(long, default_value = "16")]
+    window: usize,
+    /// Max number of calls grouped into one Utility::batch_all extrinsic if the runtime has it.
+    /// 1 disables batching.
+    #[clap(long, default_value = "32")]
+    batch_size: usize,
+    /// Number of times a transaction is submitted again after a transient failure, e.g. a
+    /// connection drop or a rejection by the transaction pool.
+    #[clap(long, default_value = "3")]
+    retries: u32,
+    /// Delay in milliseconds before the first retry, doubled for each next one.
+    #[clap(long, default_value = "2000")]
+    backoff: u64,
+    /// Seconds in which a transaction must be included in a block, otherwise it is retried.
+    #[clap(long, default_value = "60")]
+    timeout: u64,
+  },
+  /// Copy the game from one network to another.
+  Clone {
+    /// RPC endpoint of the node of the network from which the game is copied.
+    #[clap(long, required = true)]
+    from_endpoint: String,
+    /// Address of the copied game in SS58 format.
+    #[clap(long, short, required = true)]
+    organization: String,
+    /// RPC endpoint of the node of the network to which the game is copied.
+    #[clap(long, default_value = "ws://127.0.0.1:9944")]
+    to_endpoint: String,
+    /// Organization account key seed of the new game. May be a secret seed or secret URI.
+    #[clap(long, short = 's', required = true)]
+    organization_seed: String,
+    /// Game manager on whose behalf the new game will be configured. May be a secret seed or
+    /// secret URI.
+    #[clap(long, short = 'm', required = true)]
+    manager_seed: String,
+    /// Block number or 0x-prefixed block hash at which the game is exported. [default: the best block]
+    #[clap(long)]
+    at: Option<finalbiome_impex::BlockAt>,
+    /// Export at the latest finalized block instead of the best one.
+    #[clap(long, conflicts_with = "at")]
+    finalized: bool,
+    /// Number of storage keys requested from the node at once.
+    #[clap(long, default_value = "100")]
+    page_size: u32,
+    /// Number of requests sent to the node concurrently.
+    #[clap(long, default_value = "8")]
+    parallelism: usize,
+    /// Path to the file to which the exported game spec will be saved. The progress of the import
+    /// is recorded in <GAME_SPEC>.journal.json
+    #[clap(long, short)]
+    game_spec: Option<PathBuf>,
+    /// Path to the file to which the ids of the created assets and the results of the submitted
+    /// calls will be written.
+    #[clap(long)]
+    report: Option<PathBuf>,
+    /// Seed template of the player accounts with the `n` placeholder in braces, which is replaced
+    /// with the number of the player. A player is onboarded to the new game for each user of the
+    /// copied game.
+    #[clap(long, short = 'p')]
+    players: Option<String>,
+    /// Number of calls of the same signer submitted without waiting for their inclusion.
+    #[clap(long, default_value = "16")]
+    window: usize,
+    /// Max number of calls grouped into one Utility::batch_all extrinsic if the runtime has it.
+    /// 1 disables batching.
+    #[clap(long, default_value = "32")]
+    batch_size: usize,
+    /// Number of times a transaction is submitted again after a transient failure, e.g. a
+    /// connection drop or a rejection by the transaction pool.
+    #[clap(long, default_value = "3")]
+    retries: u32,
+    /// Delay in milliseconds before the first retry, doubled for each next one.
+    #[clap(long, default_value = "2000")]
+    backoff: u64,
+    /// Seconds in which a transaction must be included in a block, otherwise it is retried.
+    #[clap(long, default_value = "60")]
+    timeout: u64,
+  },
+  /// Destroy the game in the network: clear the onboarding assets, remove the attributes,
+  /// destroy the NFA and FA and remove the members.
+  Teardown {
+    /// RPC endpoint of the network node.
+    #[clap(long, short, default_value = "ws://127.0.0.1:9944")]
+    endpoint: String,
+    /// Game address in SS58 format.
+    #[clap(long, short, required = true)]
+    organization: String,
+    /// Game manager on whose behalf the assets are destroyed. May be a secret seed or secret URI.
+    #[clap(long, short = 'm', required = true)]
+    manager_seed: String,
+    /// Game organization account key seed. The members are removed only if it is given.
+    #[clap(long, short = 's')]
+    organization_seed: Option<String>,
+    /// Path to the file to which the results of the submitted calls will be written.
+    #[clap(long, conflicts_with = "dry_run")]
+    report: Option<PathBuf>,
+    /// Print the calls which would be submitted without submitting anything.
+    #[clap(long, default_value = "false")]
+    dry_run: bool,
+    /// Print the dry run plan as JSON.
+    #[clap(long, default_value = "false", requires = "dry_run")]
+    json: bool,
+    /// Do not ask for confirmation.
+    #[clap(long, short = 'y', default_value = "false")]
+    yes: bool,
+    /// Number of calls of the same signer submitted without waiting for their inclusion.
+    #[clap(long, default_value = "16")]
+    window: usize,
+    /// Max number of calls grouped into one Utility::batch_all extrinsic if the runtime has it.
+    /// 1 disables batching.
+    #[clap(long, default_value = "32")]
+    batch_size: usize,
+    /// Number of times a transaction is submitted again after a transient failure, e.g. a
+    /// connection drop or a rejection by the transaction pool.
+    #[clap(long, default_value = "3")]
+    retries: u32,
+    /// Delay in milliseconds before the first retry, doubled for each next one.
+    #[clap(long, default_value = "2000")]
+    backoff: u64,
+    /// Seconds in which a transaction must be included in a block, otherwise it is retried.
+    #[clap(long, default_value = "60")]
+    timeout: u64,
+  },
+  /// Compare two game specs. Each of them may be a file or a game in the network.
+  Diff {
+    /// Path to the first game spec file.
+    #[clap(long, required_unless_present = "left_endpoint")]
+    left: Option<PathBuf>,
+    /// RPC endpoint of the network node of the first game.
+    #[clap(long, conflicts_with = "left", requires = "left_organization")]
+    left_endpoint: Option<String>,
+    /// Address of the first game in SS58 format.
+    #[clap(long, requires = "left_endpoint")]
+    left_organization: Option<String>,
+    /// Path to the second game spec file.
+    #[clap(long, required_unless_present = "right_endpoint")]
+    right: Option<PathBuf>,
+    /// RPC endpoint of the network node of the second game.
+    #[clap(long, conflicts_with = "right", requires = "right_organization")]
+    right_endpoint: Option<String>,
+    /// Address of the second game in SS58 format.
+    #[clap(long, requires = "right_endpoint")]
+    right_organization: Option<String>,
+    /// Print the differences as JSON.
+    #[clap(long, default_value = "false")]
+    json: bool,
+  },
+  /// Print running mechanics and pending top-ups of the game in the network.
+  Inspect {
+    /// RPC endpoint of the network node.
+    #[clap(long, short, default_value = "ws://127.0.0.1:9944")]
+    endpoint: String,
+    /// Game address in SS58 format.
+    #[clap(long, short, required = true)]
+    organization: String,
+    /// Block number or 0x-prefixed block hash at which the game is inspected. [default: the best block]
+    #[clap(long)]
+    at: Option<finalbiome_impex::BlockAt>,
+    /// Inspect at the latest finalized block instead of the best one.
+    #[clap(long, conflicts_with = "at")]
+    finalized: bool,
+    /// Number of storage keys requested from the node at once.
+    #[clap(long, default_value = "100")]
+    page_size: u32,
+    /// Number of requests sent to the node concurrently.
+    #[clap(long, default_value = "8")]
+    parallelism: usize,
+    /// Print the state as JSON.
+    #[clap(long, default_value = "false")]
+    json: bool,
+  },
+  /// Upgrade game spec files to the current format version in place.
+  Migrate {
+    /// Paths to the game spec files which will be upgraded.
+    #[clap(required = true)]
+    game_specs: Vec<PathBuf>,
+  },
+  /// Print JSON Schema of the game spec file.
+  Schema {
+    /// Path to the file to which the schema will be written instead of printing.
+    #[clap(long, short)]
+    output: Option<PathBuf>,
+  },
+  /// Check game spec file without connecting to the network.
+  Validate {
+    /// Path to the game file which will be checked.
+    #[clap(long, short, required = true)]
+    game_spec: PathBuf,
+    /// Print found problems as JSON.
+    #[clap(long, default_value = "false")]
+    json: bool,
   },
 }
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+async fn main() {
   let impex = Impex::parse();
 
+  if let Err(e) = run(impex).await {
+    eprintln!("Error: {}", e);
+    std::process::exit(1);
+  }
+}
+
+async fn run(impex: Impex) -> Result<(), Box<dyn std::error::Error>> {
   match impex {
     Impex::Export {
       endpoint,
       organization,
       game_spec,
       overwrite,
-    } => finalbiome_impex::export_game_spec(endpoint, organization, game_spec, overwrite).await,
+      format,
+      at,
+      finalized,
+      page_size,
+      parallelism,
+      include_users,
+      include_balances,
+      include_instances,
+    } => {
+      let at = match finalized {
+        true => finalbiome_impex::BlockAt::Finalized,
+        false => at.unwrap_or_default(),
+      };
+      let options = finalbiome_impex::ExportOptions {
+        overwrite,
+        format,
+        at,
+        fetch: finalbiome_impex::FetchOptions {
+          page_size,
+          parallelism,
+        },
+        include_users,
+        include_balances,
+        include_instances,
+      };
+      finalbiome_impex::export_game_spec(endpoint, organization, game_spec, options).await?;
+      Ok(())
+    },
     Impex::Import {
       endpoint,
       game_spec,
       organization_seed,
       manager_seed,
+      journal,
+      resume,
+      report,
+      dry_run,
+      json,
+      offline,
+      players,
+      window,
+      batch_size,
+      retries,
+      backoff,
+      timeout,
+      teardown_on_failure,
+      skip_balances,
+      skip_instances,
+    } => {
+      let options = finalbiome_impex::ImportOptions {
+        resume: resume.is_some(),
+        journal: resume.or(journal),
+        players,
+        submit: finalbiome_impex::SubmitOptions {
+          window,
+          batch_size,
+          retries,
+          backoff: Duration::from_millis(backoff),
+          timeout: Duration::from_secs(timeout),
+        },
+        teardown_on_failure,
+        skip_balances,
+        skip_instances,
+      };
+      if dry_run {
+        let endpoint = (!offline).then_some(endpoint);
+        let plan = finalbiome_impex::plan_import(
+          endpoint,
+          game_spec,
+          organization_seed,
+          manager_seed,
+          &options,
+        )
+        .await?;
+        if json {
+          println!("{}", serde_json::to_string_pretty(&plan)?);
+        } else {
+          print!("{}", plan);
+        }
+        return Ok(());
+      }
+      let import_report = finalbiome_impex::import_game_spec(
+        endpoint,
+        game_spec,
+        organization_seed,
+        manager_seed,
+        options,
+      )
+      .await?;
+      print_id_mapping(&import_report);
+      if let Some(report) = report {
+        import_report.save(report)?;
+      }
+      Ok(())
+    },
+    Impex::Apply {
+      endpoint,
+      game_spec,
+      organization_seed,
+      manager_seed,
+      report,
+      dry_run,
+      json,
+      window,
+      batch_size,
+      retries,
+      backoff,
+      timeout,
+    } => {
+      if dry_run {
+        let plan =
+          finalbiome_impex::plan_apply(endpoint, game_spec, organization_seed, manager_seed)
+            .await?;
+        if json {
+          println!("{}", serde_json::to_string_pretty(&plan)?);
+        } else {
+          print!("{}", plan);
+        }
+        return Ok(());
+      }
+      let options = finalbiome_impex::SubmitOptions {
+        window,
+        batch_size,
+        retries,
+        backoff: Duration::from_millis(backoff),
+        timeout: Duration::from_secs(timeout),
+      };
+      let apply_report = finalbiome_impex::apply_game_spec(
+        endpoint,
+        game_spec,
+        organization_seed,
+        manager_seed,
+        options,
+      )
+      .await?;
+      if let Some(report) = report {
+        apply_report.save(report)?;
+      }
+      Ok(())
+    },
+    Impex::Teardown {
+      endpoint,
+      organization,
+      manager_seed,
+      organization_seed,
+      report,
+      dry_run,
+      json,
+      yes,
+      window,
+      batch_size,
+      retries,
+      backoff,
+      timeout,
+    } => {
+      let options = finalbiome_impex::TeardownOptions {
+        organization_seed,
+        submit: finalbiome_impex::SubmitOptions {
+          window,
+          batch_size,
+          retries,
+          backoff: Duration::from_millis(backoff),
+          timeout: Duration::from_secs(timeout),
+        },
+      };
+      if dry_run || !yes {
+        let plan = finalbiome_impex::plan_teardown(
+          endpoint.clone(),
+          organization.clone(),
+          manager_seed.clone(),
+          &options,
+        )
+        .await?;
+        if json {
+          println!("{}", serde_json::to_string_pretty(&plan)?);
+        } else {
+          print!("{}", plan);
+        }
+        if dry_run {
+          return Ok(());
+        }
+        if plan.steps.is_empty() {
+          println!("Nothing to tear down");
+          return Ok(());
+        }
+        if !confirm(&format!("Destroy the game {}?", organization))? {
+          println!("Teardown cancelled");
+          return Ok(());
+        }
+      }
+      let teardown_report =
+        finalbiome_impex::teardown(endpoint, organization, manager_seed, options).await?;
+      if let Some(report) = report {
+        teardown_report.save(report)?;
+      }
+      Ok(())
+    },
+    Impex::Clone {
+      from_endpoint,
+      organization,
+      to_endpoint,
+      organization_seed,
+      manager_seed,
+      at,
+      finalized,
+      page_size,
+      parallelism,
+      game_spec,
+      report,
+      players,
+      window,
+      batch_size,
+      retries,
+      backoff,
+      timeout,
     } => {
-      finalbiome_impex::import_game_spec(endpoint, game_spec, organization_seed, manager_seed).await
+      let at = match finalized {
+        true => finalbiome_impex::BlockAt::Finalized,
+        false => at.unwrap_or_default(),
+      };
+      let options = finalbiome_impex::CloneOptions {
+        at,
+        fetch: finalbiome_impex::FetchOptions {
+          page_size,
+          parallelism,
+        },
+        // the users are needed only to onboard the players
+        include_users: players.is_some(),
+        players,
+        game_spec,
+        submit: finalbiome_impex::SubmitOptions {
+          window,
+          batch_size,
+          retries,
+          backoff: Duration::from_millis(backoff),
+          timeout: Duration::from_secs(timeout),
+        },
+      };
+      let clone_report = finalbiome_impex::clone_game(
+        from_endpoint,
+        organization,
+        to_endpoint,
+        organization_seed,
+        manager_seed,
+        options,
+      )
+      .await?;
+      print_id_mapping(&clone_report);
+      if let Some(report) = report {
+        clone_report.save(report)?;
+      }
+      Ok(())
     },
+    Impex::Diff {
+      left,
+      left_endpoint,
+      left_organization,
+      right,
+      right_endpoint,
+      right_organization,
+      json,
+    } => {
+      let left = spec_source(left, left_endpoint, left_organization);
+      let right = spec_source(right, right_endpoint, right_organization);
+      let diff = finalbiome_impex::diff_game_specs(left, right).await?;
+      if json {
+        println!("{}", serde_json::to_string_pretty(&diff)?);
+      } else if diff.is_empty() {
+        println!("Game specs are equal");
+      } else {
+        print!("{}", diff);
+      }
+      Ok(())
+    },
+    Impex::Inspect {
+      endpoint,
+      organization,
+      at,
+      finalized,
+      page_size,
+      parallelism,
+      json,
+    } => {
+      let at = match finalized {
+        true => finalbiome_impex::BlockAt::Finalized,
+        false => at.unwrap_or_default(),
+      };
+      let options = finalbiome_impex::InspectOptions {
+        at,
+        fetch: finalbiome_impex::FetchOptions {
+          page_size,
+          parallelism,
+        },
+      };
+      let inspection = finalbiome_impex::inspect_game(endpoint, organization, options).await?;
+      if json {
+        println!("{}", serde_json::to_string_pretty(&inspection)?);
+      } else {
+        print!("{}", inspection);
+      }
+      Ok(())
+    },
+    Impex::Migrate { game_specs } => {
+      for game_spec in game_specs {
+        if finalbiome_impex::migrate_game_spec(game_spec.clone())? {
+          println!("{} has been upgraded", game_spec.display());
+        } else {
+          println!("{} is up to date", game_spec.display());
+        }
+      }
+      Ok(())
+    },
+    Impex::Schema { output } => {
+      let schema = serde_json::to_string_pretty(&finalbiome_impex::game_spec_schema()?)?;
+      match output {
+        Some(path) => std::fs::write(path, schema + "\n")?,
+        None => println!("{}", schema),
+      }
+      Ok(())
+    },
+    Impex::Validate { game_spec, json } => {
+      let problems = finalbiome_impex::validate_game_spec(game_spec)?;
+      if json {
+        println!("{}", serde_json::to_string_pretty(&problems)?);
+      } else {
+        for problem in &problems {
+          println!("{}", problem);
+        }
+      }
+      if problems.is_empty() {
+        if !json {
+          println!("Game spec is valid");
+        }
+        Ok(())
+      } else {
+        Err(finalbiome_impex::ImpexError::Validation(problems).into())
+      }
+    },
+  }
+}
+
+/// Build the spec source from the command line arguments, clap guarantees that either a path or
+/// an endpoint with an organization is given.
+fn spec_source(
+  path: Option<PathBuf>,
+  endpoint: Option<String>,
+  organization: Option<String>,
+) -> finalbiome_impex::SpecSource {
+  match (path, endpoint, organization) {
+    (Some(path), ..) => finalbiome_impex::SpecSource::File(path),
+    (None, Some(endpoint), Some(organization)) => finalbiome_impex::SpecSource::Network {
+      endpoint,
+      organization,
+    },
+    _ => unreachable!("checked by clap"),
+  }
+}
+
+/// Ask the user to confirm the action, only `y` or `yes` confirms it
+fn confirm(question: &str) -> std::io::Result<bool> {
+  print!("{} [y/N] ", question);
+  std::io::stdout().flush()?;
+  let mut answer = String::new();
+  std::io::stdin().read_line(&mut answer)?;
+  Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Print the ids of the spec assets and of the created ones
+fn print_id_mapping(report: &finalbiome_impex::ImportReport) {
+  println!("Organization: {}", report.organization);
+  for (orig, created) in &report.fa_ids_map {
+    println!("FA {} -> {}", orig.0, created.0);
+  }
+  for (orig, created) in &report.nfa_ids_map {
+    println!("NFA {} -> {}", orig.0, created.0);
   }
 }
@@ -0,0 +1,216 @@
+use serde::Deserialize;
+
+use crate::{
+  format::SpecFormat,
+  game_spec::{GameSpec, OrganizationMembers},
+  handle::Handles,
+  AttributesDetails, FungibleAssetIds, ImpexError, NonFungibleClassDetails, OrganizationDetails,
+  ResultOf,
+};
+
+/// Version of the game spec format written by this version of the utility.
+///
+/// History:
+/// - 1: initial format, `version` holds the version of the node
+/// - 2: `spec_format_version` is added, `version` is renamed to `node_version`
+/// - 3: assets may be declared and referenced by symbolic handles instead of the numeric ids
+/// - 4: `asset_ids` keeps the ids of the assets declared by handles
+pub const SPEC_FORMAT_VERSION: u32 = 4;
+
+/// Fields which are read before the spec itself to find out its format
+#[derive(Deserialize)]
+struct SpecHeader {
+  /// Absent in the first version of the format
+  spec_format_version: Option<u32>,
+}
+
+/// Game spec in the format of version 1
+#[derive(Deserialize)]
+struct GameSpecV1 {
+  version: String,
+  hash: String,
+  organization_details: OrganizationDetails,
+  organization_members: OrganizationMembers,
+  fa: FungibleAssetIds,
+  nfa: NonFungibleClassDetails,
+  attributes: AttributesDetails,
+}
+
+impl From<GameSpecV1> for GameSpec {
+  fn from(spec: GameSpecV1) -> GameSpec {
+    GameSpec {
+      spec_format_version: SPEC_FORMAT_VERSION,
+      node_version: spec.version,
+      hash: spec.hash,
+      organization_details: spec.organization_details,
+      organization_members: spec.organization_members,
+      fa: spec.fa,
+      nfa: spec.nfa,
+      attributes: spec.attributes,
+      users: None,
+      balances: None,
+      instances: None,
+      asset_ids: None,
+    }
+  }
+}
+
+/// Reads the game spec of any known format version and upgrades it to the current one.
+///
+/// Returns the spec and the format version it was stored in.
+/// Asset handles are resolved to the ids, numeric ids are kept as is.
+pub(crate) fn read_game_spec(content: &str, format: SpecFormat) -> ResultOf<(GameSpec, u32)> {
+  let header: SpecHeader = format.parse(content)?;
+  let version = header.spec_format_version.unwrap_or(1);
+  let handles = Handles::declared(content, format)?;
+  let game_spec = match version {
+    1 => handles
+      .scope(|| format.parse::<GameSpecV1>(content))?
+      .into(),
+    // the formats differ only by the handles and their ids, which are not used by the older ones
+    2 | 3 | SPEC_FORMAT_VERSION => GameSpec {
+      spec_format_version: SPEC_FORMAT_VERSION,
+      ..handles.scope(|| format.parse::<GameSpec>(content))?
+    },
+    _ => {
+      return Err(ImpexError::Spec(format!(
+        "Game spec format version {} is not supported, the latest known is {}",
+        version, SPEC_FORMAT_VERSION
+      )))
+    },
+  };
+  Ok((game_spec, version))
+}
+
+/// Writes the game spec in the current format version.
+///
+/// Assets are written with the handles generated from their names, the ids are recorded next to
+/// them so the spec is read back with the same ids.
+pub(crate) fn write_game_spec(game_spec: &GameSpec, format: SpecFormat) -> ResultOf<String> {
+  let handles = Handles::generate(game_spec);
+  let game_spec = GameSpec {
+    asset_ids: Some(handles.asset_ids()),
+    ..game_spec.clone()
+  };
+  handles.scope(|| format.write(&game_spec))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Spec of the first format version, as written by the first versions of the utility
+  const SPEC_V1: &str = r#"{
+    "version": "0.1",
+    "hash": "0x00",
+    "organization_details": { "name": [71, 97, 109, 101], "onboarding_assets": [{ "Fa": [3, 5] }] },
+    "organization_members": [],
+    "fa": [[3, {
+      "owner": "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY",
+      "supply": 0, "accounts": 0, "references": 0,
+      "name": [71, 111, 108, 100],
+      "top_upped": null, "cup_global": null, "cup_local": null
+    }]],
+    "nfa": [],
+    "attributes": []
+  }"#;
+
+  #[test]
+  fn version_1_is_upgraded() {
+    let (game_spec, version) = read_game_spec(SPEC_V1, SpecFormat::Json).unwrap();
+    assert_eq!(version, 1);
+    assert_eq!(game_spec.spec_format_version, SPEC_FORMAT_VERSION);
+    assert_eq!(game_spec.node_version, "0.1");
+    assert_eq!(game_spec.organization_details.name.0, b"Game");
+    assert_eq!(game_spec.fa[0].0 .0, 3);
+    assert_eq!(game_spec.fa[0].1.name.0, b"Gold");
+    assert!(game_spec.users.is_none());
+  }
+
+  #[test]
+  fn version_2_is_read_as_is() {
+    let content = SPEC_V1.replace(
+      r#""version": "0.1""#,
+      r#""spec_format_version": 2, "node_version": "0.2""#,
+    );
+    let (game_spec, version) = read_game_spec(&content, SpecFormat::Json).unwrap();
+    assert_eq!(version, 2);
+    assert_eq!(game_spec.spec_format_version, SPEC_FORMAT_VERSION);
+    assert_eq!(game_spec.node_version, "0.2");
+    assert_eq!(game_spec.fa[0].0 .0, 3);
+  }
+
+  #[test]
+  fn version_3_resolves_handles() {
+    let content = SPEC_V1
+      .replace(
+        r#""version": "0.1""#,
+        r#""spec_format_version": 3, "node_version": "0.3""#,
+      )
+      .replace("[[3, {", r#"[["gold", {"#)
+      .replace(r#"{ "Fa": [3, 5] }"#, r#"{ "Fa": ["gold", 5] }"#);
+    let (game_spec, version) = read_game_spec(&content, SpecFormat::Json).unwrap();
+    assert_eq!(version, 3);
+    assert_eq!(game_spec.fa[0].0 .0, 0);
+    let onboarding = game_spec.organization_details.onboarding_assets.unwrap();
+    assert!(matches!(onboarding.0[..], [crate::AirDropAsset::Fa(id, _)] if id.0 == 0));
+  }
+
+  #[test]
+  fn written_spec_is_read_with_same_ids() {
+    let mut game_spec = crate::game_spec::tests::game_spec(|_| {});
+    // ids of another network, the declaration order differs from the order of the ids
+    let (gold, energy) = (crate::FungibleAssetId(7), crate::FungibleAssetId(3));
+    let sword = crate::NonFungibleClassId(5);
+    game_spec.fa[0].0 = gold;
+    game_spec.fa[1].0 = energy;
+    game_spec.nfa[0].0 = sword;
+    game_spec.organization_details.onboarding_assets = None;
+    game_spec.nfa[0].1.purchased.as_mut().unwrap().offers.0[0].fa = gold;
+    for (class, ..) in &mut game_spec.attributes {
+      *class = sword;
+    }
+
+    let content = write_game_spec(&game_spec, SpecFormat::Json).unwrap();
+    assert!(content.contains(r#""fa":[["gold","#), "{}", content);
+    let (read, version) = read_game_spec(&content, SpecFormat::Json).unwrap();
+    assert_eq!(version, SPEC_FORMAT_VERSION);
+    assert_eq!(
+      serde_json::to_string(&read).unwrap(),
+      serde_json::to_string(&game_spec).unwrap()
+    );
+  }
+
+  #[test]
+  fn recorded_id_must_be_free() {
+    let mut content: serde_json::Value = serde_json::from_str(SPEC_V1).unwrap();
+    content["spec_format_version"] = 4.into();
+    content["node_version"] = "0.4".into();
+    let silver = content["fa"][0].clone();
+    content["fa"][0][0] = "gold".into();
+    content["asset_ids"] = serde_json::json!({ "fa": { "gold": 3, "copper": 4 } });
+    let (game_spec, _) = read_game_spec(&content.to_string(), SpecFormat::Json).unwrap();
+    assert_eq!(game_spec.fa[0].0 .0, 3);
+
+    // the numeric id 3 is taken by another asset
+    content["fa"].as_array_mut().unwrap().push(silver);
+    let result = read_game_spec(&content.to_string(), SpecFormat::Json);
+    assert!(matches!(
+      result,
+      Err(ImpexError::Spec(message)) if message.contains("Id 3 of handle gold")
+    ));
+  }
+
+  #[test]
+  fn unknown_version_is_rejected() {
+    let content = SPEC_V1.replace(
+      r#""version": "0.1""#,
+      r#""spec_format_version": 99, "node_version": "9.9""#,
+    );
+    let result = read_game_spec(&content, SpecFormat::Json);
+    assert!(matches!(
+      result,
+      Err(ImpexError::Spec(message)) if message.contains("version 99 is not supported")
+    ));
+  }
+}
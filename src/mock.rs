@@ -0,0 +1,862 @@
+use async_trait::async_trait;
+use parity_scale_codec::{Decode, Encode};
+use sp_core::{sr25519, Pair, H256};
+use sp_runtime::{AccountId32, MultiAddress};
+use std::{
+  collections::{BTreeMap, HashMap},
+  ops::Bound,
+  sync::{
+    atomic::{AtomicUsize, Ordering},
+    Mutex, MutexGuard,
+  },
+  time::Duration,
+};
+
+use crate::{
+  backend::parse_block_hash,
+  finalbiome::{self, runtime_types},
+  AirDropAsset, AttributeKey, BlockAt, BoundedVec, ChainBackend, EventReport, FungibleAssetDetails,
+  FungibleAssetId, ImpexError, NonFungibleAssetDetails, NonFungibleClassId, NonFungibleDetails,
+  OrganizationDetails, Rejection, ResultOf, RuntimeCall, RuntimeEvent, SubmittedCall,
+};
+
+use runtime_types::{
+  pallet_fungible_assets::{
+    pallet::{Call as FaCall, Event as FaEvent},
+    types::{AssetAccount, ExistenceReason},
+  },
+  pallet_non_fungible_assets::pallet::{Call as NfaCall, Event as NfaEvent},
+  pallet_organization_identity::pallet::{Call as OrganizationCall, Event as OrganizationEvent},
+  pallet_support::{
+    characteristics::Characteristic,
+    types::{
+      fungible_asset_balance::FungibleAssetBalance, non_fungible_asset_id::NonFungibleAssetId,
+    },
+    AttributeValue, Locker,
+  },
+  sp_runtime::bounded::weak_bounded_vec::WeakBoundedVec,
+};
+
+/// Max length of the organization name, `StringLimit` of the runtime
+const ORGANIZATION_NAME_LIMIT: usize = 36;
+/// Max number of the organization members, `MaxMembers` of the runtime
+const MAX_MEMBERS: u8 = 3;
+/// Max length of the FA name, `NameLimit` of the runtime
+const FA_NAME_LIMIT: usize = 32;
+
+type Storage = BTreeMap<Vec<u8>, Vec<u8>>;
+
+/// Chain kept in memory which simulates the pallets of the game, e.g. for the tests.
+///
+/// Organizations, members, users, FA, NFA classes and their attributes are created by the
+/// submitted calls. Ids are allocated and events are emitted as the runtime does. Each submitted
+/// call produces a new block, so the state at the earlier blocks can still be read. The nonces of
+/// the signers are checked as the transaction pool does. Like the FinalBiome runtime, the chain
+/// has no `Utility` pallet unless it's created with [`MemoryChain::with_utility`].
+pub struct MemoryChain {
+  blocks: Mutex<Vec<Block>>,
+  nonces: Mutex<HashMap<AccountId32, u32>>,
+  utility: bool,
+  accepted: AtomicUsize,
+  dropped: AtomicUsize,
+  outdated: AtomicUsize,
+  delayed: Mutex<(usize, usize, Duration)>,
+}
+
+struct Block {
+  hash: H256,
+  storage: Storage,
+}
+
+impl Default for MemoryChain {
+  fn default() -> Self {
+    MemoryChain::new()
+  }
+}
+
+impl MemoryChain {
+  /// Creates the chain with the empty genesis block
+  pub fn new() -> MemoryChain {
+    MemoryChain {
+      blocks: Mutex::new(vec![Block {
+        hash: block_hash(0),
+        storage: Storage::new(),
+      }]),
+      nonces: Mutex::new(HashMap::new()),
+      utility: false,
+      accepted: AtomicUsize::new(0),
+      dropped: AtomicUsize::new(0),
+      outdated: AtomicUsize::new(0),
+      delayed: Mutex::new((0, 0, Duration::ZERO)),
+    }
+  }
+
+  /// Adds the `Utility` pallet to the runtime, so the calls can be submitted in batches
+  pub fn with_utility(mut self) -> MemoryChain {
+    self.utility = true;
+    self
+  }
+
+  /// Writes the value by the storage key in a new block.
+  ///
+  /// Allows to set the state which cannot be created by the calls, e.g. the FA balances.
+  pub fn insert<V: Encode>(&self, key: Vec<u8>, value: &V) {
+    let mut blocks = self.blocks();
+    let mut storage = blocks.last().map(|b| b.storage.clone()).unwrap_or_default();
+    storage.insert(key, value.encode());
+    push_block(&mut blocks, storage);
+  }
+
+  /// Returns the number of the best block
+  pub fn best_number(&self) -> u64 {
+    self.blocks().len() as u64 - 1
+  }
+
+  fn blocks(&self) -> MutexGuard<'_, Vec<Block>> {
+    // the blocks are never left half written, so a poisoned lock is still usable
+    self.blocks.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  fn nonces(&self) -> MutexGuard<'_, HashMap<AccountId32, u32>> {
+    self.nonces.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  /// Makes the transaction pool drop the next `count` submitted transactions without
+  /// dispatching them, e.g. to test the retries.
+  pub fn drop_transactions(&self, count: usize) {
+    self.drop_transactions_after(0, count);
+  }
+
+  /// Makes the transaction pool accept the next `accepted` transactions and then drop `count`
+  /// transactions, e.g. to fail the calls which follow the given ones.
+  pub fn drop_transactions_after(&self, accepted: usize, count: usize) {
+    self.accepted.store(accepted, Ordering::SeqCst);
+    self.dropped.store(count, Ordering::SeqCst);
+  }
+
+  /// Makes the transaction pool reject the next `count` submitted transactions as outdated, as
+  /// if their nonces were used by other transactions of the signers.
+  pub fn outdate_transactions(&self, count: usize) {
+    self.outdated.store(count, Ordering::SeqCst);
+  }
+
+  /// Makes the transactions which follow the next `included` ones report their inclusion after
+  /// the delay, up to `count` transactions, e.g. to time out the transactions included in blocks.
+  pub fn delay_transactions_after(&self, included: usize, count: usize, delay: Duration) {
+    *self.delayed.lock().unwrap_or_else(|e| e.into_inner()) = (included, count, delay);
+  }
+
+  /// Waits before the transaction included in a block is reported, if it's delayed
+  async fn report_inclusion(&self) {
+    let delay = {
+      let mut delayed = self.delayed.lock().unwrap_or_else(|e| e.into_inner());
+      let (included, count, delay) = &mut *delayed;
+      if let Some(rest) = included.checked_sub(1) {
+        *included = rest;
+        None
+      } else if let Some(rest) = count.checked_sub(1) {
+        *count = rest;
+        Some(*delay)
+      } else {
+        None
+      }
+    };
+    if let Some(delay) = delay {
+      tokio::time::sleep(delay).await;
+    }
+  }
+
+  /// Checks the nonce of the transaction as the transaction pool does and increments the nonce
+  /// of the account. The nonce is used even if the call fails.
+  fn use_nonce(&self, who: &AccountId32, nonce: Option<u32>) -> ResultOf<()> {
+    let accepted = self
+      .accepted
+      .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |count| {
+        count.checked_sub(1)
+      });
+    let dropped = accepted.is_err()
+      && self
+        .dropped
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |count| {
+          count.checked_sub(1)
+        })
+        .is_ok();
+    if dropped {
+      return Err(ImpexError::Rejected(Rejection::Dropped));
+    }
+    let outdated = self
+      .outdated
+      .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |count| {
+        count.checked_sub(1)
+      });
+    if outdated.is_ok() {
+      return Err(ImpexError::Rejected(Rejection::Stale));
+    }
+    let mut nonces = self.nonces();
+    let next_nonce = nonces.entry(who.clone()).or_default();
+    match nonce {
+      Some(nonce) if nonce < *next_nonce => Err(ImpexError::Rejected(Rejection::Stale)),
+      Some(nonce) if nonce > *next_nonce => Err(ImpexError::Rejected(Rejection::Future)),
+      _ => {
+        *next_nonce += 1;
+        Ok(())
+      },
+    }
+  }
+}
+
+#[async_trait]
+impl ChainBackend for MemoryChain {
+  async fn block_hash(&self, at: &BlockAt) -> ResultOf<H256> {
+    let blocks = self.blocks();
+    let block = match at {
+      BlockAt::Best | BlockAt::Finalized => blocks.last(),
+      BlockAt::Number(number) => blocks.get(*number as usize),
+      BlockAt::Hash(hex) => {
+        let hash = parse_block_hash(hex)?;
+        blocks.iter().find(|block| block.hash == hash)
+      },
+    };
+    block
+      .map(|block| block.hash)
+      .ok_or_else(|| ImpexError::NotFound(format!("Block {:?} not found", at)))
+  }
+
+  async fn block_number(&self, hash: H256) -> ResultOf<u64> {
+    self
+      .blocks()
+      .iter()
+      .position(|block| block.hash == hash)
+      .map(|number| number as u64)
+      .ok_or_else(|| ImpexError::NotFound("Cannot fetch header of the block".to_string()))
+  }
+
+  async fn node_version(&self) -> ResultOf<String> {
+    Ok(format!("{}-memory", env!("CARGO_PKG_VERSION")))
+  }
+
+  async fn fetch_keys(
+    &self,
+    prefix: &[u8],
+    count: u32,
+    start_key: Option<&[u8]>,
+    at: H256,
+  ) -> ResultOf<Vec<Vec<u8>>> {
+    let blocks = self.blocks();
+    let storage = storage_at(&blocks, at)?;
+    let from = match start_key {
+      Some(start_key) => Bound::Excluded(start_key.to_vec()),
+      None => Bound::Included(prefix.to_vec()),
+    };
+    Ok(
+      storage
+        .range((from, Bound::Unbounded))
+        .map(|(key, _)| key)
+        .take_while(|key| key.starts_with(prefix))
+        .take(count as usize)
+        .cloned()
+        .collect(),
+    )
+  }
+
+  async fn fetch_values(&self, keys: &[Vec<u8>], at: H256) -> ResultOf<Vec<Option<Vec<u8>>>> {
+    let blocks = self.blocks();
+    let storage = storage_at(&blocks, at)?;
+    Ok(keys.iter().map(|key| storage.get(key).cloned()).collect())
+  }
+
+  async fn account_nonce(&self, account: &AccountId32) -> ResultOf<u32> {
+    Ok(self.nonces().get(account).copied().unwrap_or_default())
+  }
+
+  async fn submit(
+    &self,
+    signer: &sr25519::Pair,
+    call: RuntimeCall,
+    nonce: Option<u32>,
+  ) -> ResultOf<SubmittedCall> {
+    let who: AccountId32 = signer.public().into();
+    let submitted = {
+      let mut blocks = self.blocks();
+      self.use_nonce(&who, nonce)?;
+      let mut state = State(blocks.last().map(|b| b.storage.clone()).unwrap_or_default());
+      let extrinsic_hash = H256(sp_core::hashing::blake2_256(
+        &(blocks.len() as u32, &who, &call).encode(),
+      ));
+      let events = state.dispatch(who, call)?;
+      let block_hash = push_block(&mut blocks, state.0);
+      SubmittedCall {
+        block_hash,
+        extrinsic_hash,
+        reports: events.iter().map(event_report).collect(),
+        events,
+      }
+    };
+    self.report_inclusion().await;
+    Ok(submitted)
+  }
+
+  async fn supports_batch(&self) -> ResultOf<bool> {
+    Ok(self.utility)
+  }
+
+  async fn submit_batch(
+    &self,
+    signer: &sr25519::Pair,
+    calls: Vec<RuntimeCall>,
+    nonce: Option<u32>,
+  ) -> ResultOf<Vec<SubmittedCall>> {
+    if !self.utility {
+      return Err(ImpexError::InvalidArgument(
+        "Runtime has no Utility::batch_all call".to_string(),
+      ));
+    }
+    let who: AccountId32 = signer.public().into();
+    let submitted = {
+      let mut blocks = self.blocks();
+      self.use_nonce(&who, nonce)?;
+      let mut state = State(blocks.last().map(|b| b.storage.clone()).unwrap_or_default());
+      let extrinsic_hash = H256(sp_core::hashing::blake2_256(
+        &(blocks.len() as u32, &who, &calls).encode(),
+      ));
+      // the state is dropped if one of the calls fails
+      let mut items = vec![];
+      for call in calls {
+        items.push(state.dispatch(who.clone(), call)?);
+      }
+      let block_hash = push_block(&mut blocks, state.0);
+      items
+        .into_iter()
+        .map(|events| SubmittedCall {
+          block_hash,
+          extrinsic_hash,
+          reports: events.iter().map(event_report).collect(),
+          events,
+        })
+        .collect()
+    };
+    self.report_inclusion().await;
+    Ok(submitted)
+  }
+}
+
+/// Hash of the block with the given number
+fn block_hash(number: u32) -> H256 {
+  H256(sp_core::hashing::blake2_256(&number.encode()))
+}
+
+/// Appends the block with the given state and returns its hash
+fn push_block(blocks: &mut Vec<Block>, storage: Storage) -> H256 {
+  let hash = block_hash(blocks.len() as u32);
+  blocks.push(Block { hash, storage });
+  hash
+}
+
+fn storage_at(blocks: &[Block], hash: H256) -> ResultOf<&Storage> {
+  blocks
+    .iter()
+    .find(|block| block.hash == hash)
+    .map(|block| &block.storage)
+    .ok_or_else(|| ImpexError::NotFound(format!("Block {:?} not found", hash)))
+}
+
+/// Report of the event, the fields are written as in the `Debug` output
+fn event_report(event: &RuntimeEvent) -> EventReport {
+  let (pallet, fields) = match event {
+    RuntimeEvent::OrganizationIdentity(event) => ("OrganizationIdentity", format!("{:?}", event)),
+    RuntimeEvent::FungibleAssets(event) => ("FungibleAssets", format!("{:?}", event)),
+    RuntimeEvent::NonFungibleAssets(event) => ("NonFungibleAssets", format!("{:?}", event)),
+    event => ("", format!("{:?}", event)),
+  };
+  let variant = fields
+    .split(|c: char| !c.is_alphanumeric() && c != '_')
+    .next()
+    .unwrap_or_default()
+    .to_string();
+  EventReport {
+    pallet: pallet.to_string(),
+    variant,
+    fields,
+  }
+}
+
+/// Error of the pallet as it's returned by the node
+fn error(pallet: &str, error: &str) -> ImpexError {
+  ImpexError::Dispatch {
+    pallet: pallet.to_string(),
+    error: error.to_string(),
+    description: String::new(),
+  }
+}
+
+fn ensure(condition: bool, pallet: &str, err: &str) -> ResultOf<()> {
+  match condition {
+    true => Ok(()),
+    false => Err(error(pallet, err)),
+  }
+}
+
+const ORGANIZATION: &str = "OrganizationIdentity";
+const FA: &str = "FungibleAssets";
+const NFA: &str = "NonFungibleAssets";
+
+/// State of the chain changed by the call
+struct State(Storage);
+
+impl State {
+  fn get<V: Decode>(&self, key: Vec<u8>) -> Option<V> {
+    self
+      .0
+      .get(&key)
+      .and_then(|value| V::decode(&mut &value[..]).ok())
+  }
+
+  fn contains(&self, key: Vec<u8>) -> bool {
+    self.0.contains_key(&key)
+  }
+
+  fn put<V: Encode>(&mut self, key: Vec<u8>, value: &V) {
+    self.0.insert(key, value.encode());
+  }
+
+  fn remove(&mut self, key: Vec<u8>) {
+    self.0.remove(&key);
+  }
+
+  /// Removes the values with the given key prefix and returns their keys and values
+  fn drain_prefix(&mut self, prefix: &[u8]) -> Vec<(Vec<u8>, Vec<u8>)> {
+    let keys: Vec<_> = self
+      .0
+      .range(prefix.to_vec()..)
+      .map(|(key, _)| key.clone())
+      .take_while(|key| key.starts_with(prefix))
+      .collect();
+    keys
+      .into_iter()
+      .filter_map(|key| self.0.remove(&key).map(|value| (key, value)))
+      .collect()
+  }
+
+  /// Applies the call signed by `who` and returns the emitted events
+  fn dispatch(&mut self, who: AccountId32, call: RuntimeCall) -> ResultOf<Vec<RuntimeEvent>> {
+    match call {
+      RuntimeCall::OrganizationIdentity(call) => self.dispatch_organization(who, call),
+      RuntimeCall::FungibleAssets(call) => self.dispatch_fa(who, call),
+      RuntimeCall::NonFungibleAssets(call) => self.dispatch_nfa(who, call),
+      call => Err(ImpexError::InvalidArgument(format!(
+        "Call {:?} is not supported by the memory chain",
+        call
+      ))),
+    }
+  }
+
+  fn dispatch_organization(
+    &mut self,
+    who: AccountId32,
+    call: OrganizationCall,
+  ) -> ResultOf<Vec<RuntimeEvent>> {
+    let storage = finalbiome::storage().organization_identity();
+    let event = match call {
+      OrganizationCall::create_organization { name } => {
+        ensure(
+          name.len() <= ORGANIZATION_NAME_LIMIT,
+          ORGANIZATION,
+          "OrganizationNameTooLong",
+        )?;
+        ensure(
+          !self.contains(storage.organizations(&who).to_bytes()),
+          ORGANIZATION,
+          "OrganizationExists",
+        )?;
+        let details = OrganizationDetails {
+          name: BoundedVec(name.clone()),
+          onboarding_assets: None,
+        };
+        self.put(storage.organizations(&who).to_bytes(), &details);
+        OrganizationEvent::CreatedOrganization(name, who)
+      },
+      OrganizationCall::add_member { who: member } => {
+        self.ensure_organization(&who)?;
+        ensure(
+          !self.contains(storage.members_of(&who, &member).to_bytes()),
+          ORGANIZATION,
+          "AlreadyMember",
+        )?;
+        let count: u8 = self
+          .get(storage.member_count(&who).to_bytes())
+          .unwrap_or_default();
+        ensure(count < MAX_MEMBERS, ORGANIZATION, "MembershipLimitReached")?;
+        self.put(storage.members_of(&who, &member).to_bytes(), &());
+        self.put(storage.member_count(&who).to_bytes(), &(count + 1));
+        OrganizationEvent::MemberAdded(who, member)
+      },
+      OrganizationCall::remove_member { who: member } => {
+        self.ensure_organization(&who)?;
+        ensure(
+          self.contains(storage.members_of(&who, &member).to_bytes()),
+          ORGANIZATION,
+          "NotMember",
+        )?;
+        let count: u8 = self
+          .get(storage.member_count(&who).to_bytes())
+          .unwrap_or_default();
+        self.remove(storage.members_of(&who, &member).to_bytes());
+        self.put(
+          storage.member_count(&who).to_bytes(),
+          &count.saturating_sub(1),
+        );
+        OrganizationEvent::MemberRemoved(who, member)
+      },
+      OrganizationCall::set_onboarding_assets {
+        organization_id,
+        assets,
+      } => {
+        let mut details = self.ensure_organization(&organization_id)?;
+        ensure(
+          self.contains(storage.members_of(&organization_id, &who).to_bytes()),
+          ORGANIZATION,
+          "NotMember",
+        )?;
+        details.onboarding_assets = assets;
+        self.put(storage.organizations(&organization_id).to_bytes(), &details);
+        OrganizationEvent::UpdatedOrganization(organization_id)
+      },
+      OrganizationCall::onboarding { organization_id } => {
+        let details = self.ensure_organization(&organization_id)?;
+        ensure(
+          !self.contains(storage.users_of(&organization_id, &who).to_bytes()),
+          ORGANIZATION,
+          "AlreadyOnboarded",
+        )?;
+        self.put(storage.users_of(&organization_id, &who).to_bytes(), &());
+        // airdrop the onboarding assets to the new user
+        let mut events = vec![];
+        for asset in details.onboarding_assets.map(|a| a.0).unwrap_or_default() {
+          match asset {
+            AirDropAsset::Fa(asset_id, amount) => self.issue_fa(&who, asset_id, amount.0),
+            AirDropAsset::Nfa(class_id, attributes) => {
+              let asset_id = self.mint_nfa(&who, class_id, attributes.0)?;
+              events.push(RuntimeEvent::NonFungibleAssets(NfaEvent::Issued {
+                class_id,
+                asset_id,
+                owner: who.clone(),
+              }));
+            },
+          }
+        }
+        events.insert(
+          0,
+          RuntimeEvent::OrganizationIdentity(OrganizationEvent::Onboard(organization_id, who)),
+        );
+        return Ok(events);
+      },
+    };
+    Ok(vec![RuntimeEvent::OrganizationIdentity(event)])
+  }
+
+  fn dispatch_fa(&mut self, who: AccountId32, call: FaCall) -> ResultOf<Vec<RuntimeEvent>> {
+    let storage = finalbiome::storage().fungible_assets();
+    let event = match call {
+      FaCall::create {
+        organization_id,
+        name,
+        top_upped,
+        cup_global,
+        cup_local,
+      } => {
+        let owner = self.ensure_manager(FA, &who, organization_id)?;
+        ensure(name.len() <= FA_NAME_LIMIT, FA, "AssetNameTooLong")?;
+        let asset_id: FungibleAssetId = self
+          .get(storage.next_asset_id().to_bytes())
+          .unwrap_or(FungibleAssetId(0));
+        let details = FungibleAssetDetails {
+          owner: owner.clone(),
+          supply: FungibleAssetBalance(0),
+          accounts: 0,
+          references: 0,
+          name: BoundedVec(name),
+          top_upped: top_upped.clone(),
+          cup_global,
+          cup_local,
+        };
+        self.put(storage.assets(asset_id).to_bytes(), &details);
+        self.put(storage.assets_of(&owner, asset_id).to_bytes(), &());
+        self.put(
+          storage.next_asset_id().to_bytes(),
+          &FungibleAssetId(asset_id.0 + 1),
+        );
+        if top_upped.is_some() {
+          let mut top_upped: WeakBoundedVec<FungibleAssetId> = self
+            .get(storage.top_upped_assets().to_bytes())
+            .unwrap_or(WeakBoundedVec(vec![]));
+          top_upped.0.push(asset_id);
+          self.put(storage.top_upped_assets().to_bytes(), &top_upped);
+        }
+        FaEvent::Created { asset_id, owner }
+      },
+      FaCall::destroy {
+        organization_id,
+        asset_id,
+      } => {
+        let owner = self.ensure_manager(FA, &who, organization_id)?;
+        let details: FungibleAssetDetails = self
+          .get(storage.assets(asset_id).to_bytes())
+          .ok_or_else(|| error(FA, "NoneValue"))?;
+        ensure(details.owner == owner, FA, "NoPermission")?;
+        self.remove(storage.assets(asset_id).to_bytes());
+        self.remove(storage.assets_of(&owner, asset_id).to_bytes());
+        self.drain_prefix(&map_prefix(
+          storage.top_up_queue_root().to_root_bytes(),
+          &asset_id,
+        ));
+        if let Some(mut top_upped) =
+          self.get::<WeakBoundedVec<FungibleAssetId>>(storage.top_upped_assets().to_bytes())
+        {
+          top_upped.0.retain(|id| *id != asset_id);
+          self.put(storage.top_upped_assets().to_bytes(), &top_upped);
+        }
+        FaEvent::Destroyed { asset_id, owner }
+      },
+    };
+    Ok(vec![RuntimeEvent::FungibleAssets(event)])
+  }
+
+  fn dispatch_nfa(&mut self, who: AccountId32, call: NfaCall) -> ResultOf<Vec<RuntimeEvent>> {
+    let storage = finalbiome::storage().non_fungible_assets();
+    let event = match call {
+      NfaCall::create {
+        organization_id,
+        name,
+      } => {
+        let owner = self.ensure_manager(NFA, &who, organization_id)?;
+        let class_id: NonFungibleClassId = self
+          .get(storage.next_class_id().to_bytes())
+          .unwrap_or(NonFungibleClassId(0));
+        let details = NonFungibleDetails {
+          owner: owner.clone(),
+          instances: 0,
+          attributes: 0,
+          name: BoundedVec(name),
+          bettor: None,
+          purchased: None,
+        };
+        self.put(storage.classes(class_id).to_bytes(), &details);
+        self.put(storage.class_accounts(&owner, class_id).to_bytes(), &());
+        self.put(
+          storage.next_class_id().to_bytes(),
+          &NonFungibleClassId(class_id.0 + 1),
+        );
+        NfaEvent::Created { class_id, owner }
+      },
+      NfaCall::destroy {
+        organization_id,
+        class_id,
+      } => {
+        let owner = self.ensure_manager(NFA, &who, organization_id)?;
+        self.ensure_class(class_id, &owner)?;
+        self.remove(storage.classes(class_id).to_bytes());
+        self.remove(storage.class_accounts(&owner, class_id).to_bytes());
+        self.drain_prefix(&map_prefix(
+          storage.class_attributes_root().to_root_bytes(),
+          &class_id,
+        ));
+        NfaEvent::Destroyed { class_id }
+      },
+      NfaCall::create_attribute {
+        organization_id,
+        class_id,
+        attribute,
+      } => {
+        let owner = self.ensure_manager(NFA, &who, organization_id)?;
+        let mut details = self.ensure_class(class_id, &owner)?;
+        let key = storage
+          .class_attributes(class_id, &attribute.key)
+          .to_bytes();
+        ensure(!self.contains(key.clone()), NFA, "AttributeAlreadyExists")?;
+        self.put(key, &attribute.value);
+        details.attributes += 1;
+        self.put(storage.classes(class_id).to_bytes(), &details);
+        NfaEvent::AttributeCreated {
+          class_id,
+          key: attribute.key,
+          value: attribute.value,
+        }
+      },
+      NfaCall::remove_attribute {
+        organization_id,
+        class_id,
+        attribute_name,
+      } => {
+        let owner = self.ensure_manager(NFA, &who, organization_id)?;
+        let mut details = self.ensure_class(class_id, &owner)?;
+        let key = storage
+          .class_attributes(class_id, &attribute_name)
+          .to_bytes();
+        ensure(self.contains(key.clone()), NFA, "NoneValue")?;
+        self.remove(key);
+        details.attributes = details.attributes.saturating_sub(1);
+        self.put(storage.classes(class_id).to_bytes(), &details);
+        NfaEvent::AttributeRemoved {
+          class_id,
+          key: attribute_name,
+        }
+      },
+      NfaCall::set_characteristic {
+        organization_id,
+        class_id,
+        characteristic,
+      } => {
+        let owner = self.ensure_manager(NFA, &who, organization_id)?;
+        let mut details = self.ensure_class(class_id, &owner)?;
+        match characteristic {
+          Characteristic::Bettor(bettor) => details.bettor = bettor,
+          Characteristic::Purchased(purchased) => details.purchased = purchased,
+        }
+        self.put(storage.classes(class_id).to_bytes(), &details);
+        NfaEvent::Updated { class_id }
+      },
+    };
+    Ok(vec![RuntimeEvent::NonFungibleAssets(event)])
+  }
+
+  /// Returns the details of the organization
+  fn ensure_organization(&self, organization: &AccountId32) -> ResultOf<OrganizationDetails> {
+    let key = finalbiome::storage()
+      .organization_identity()
+      .organizations(organization)
+      .to_bytes();
+    self
+      .get(key)
+      .ok_or_else(|| error(ORGANIZATION, "NotOrganization"))
+  }
+
+  /// Checks that `who` is the organization or its member and returns the organization
+  fn ensure_manager(
+    &self,
+    pallet: &str,
+    who: &AccountId32,
+    organization_id: MultiAddress<AccountId32, ()>,
+  ) -> ResultOf<AccountId32> {
+    let MultiAddress::Id(organization) = organization_id else {
+      return Err(error(pallet, "NoPermission"));
+    };
+    let is_member = self.contains(
+      finalbiome::storage()
+        .organization_identity()
+        .members_of(&organization, who)
+        .to_bytes(),
+    );
+    ensure(
+      self.ensure_organization(&organization).is_ok() && (is_member || *who == organization),
+      pallet,
+      "NoPermission",
+    )?;
+    Ok(organization)
+  }
+
+  /// Returns the details of the class owned by the organization
+  fn ensure_class(
+    &self,
+    class_id: NonFungibleClassId,
+    owner: &AccountId32,
+  ) -> ResultOf<NonFungibleDetails> {
+    let details: NonFungibleDetails = self
+      .get(
+        finalbiome::storage()
+          .non_fungible_assets()
+          .classes(class_id)
+          .to_bytes(),
+      )
+      .ok_or_else(|| error(NFA, "UnknownClass"))?;
+    ensure(details.owner == *owner, NFA, "NoPermission")?;
+    Ok(details)
+  }
+
+  /// Adds the amount of the FA to the balance of the account
+  fn issue_fa(&mut self, who: &AccountId32, asset_id: FungibleAssetId, amount: u128) {
+    let storage = finalbiome::storage().fungible_assets();
+    let Some(mut details) = self.get::<FungibleAssetDetails>(storage.assets(asset_id).to_bytes())
+    else {
+      return;
+    };
+    let account = match self.get::<AssetAccount>(storage.accounts(who, asset_id).to_bytes()) {
+      Some(mut account) => {
+        account.balance.0 += amount;
+        account
+      },
+      None => {
+        details.accounts += 1;
+        AssetAccount {
+          balance: FungibleAssetBalance(amount),
+          reason: ExistenceReason::Sufficient,
+        }
+      },
+    };
+    details.supply.0 += amount;
+    self.put(storage.accounts(who, asset_id).to_bytes(), &account);
+    self.put(storage.assets(asset_id).to_bytes(), &details);
+  }
+
+  /// Mints the instance of the class with the attributes of the class overridden by the given ones
+  fn mint_nfa(
+    &mut self,
+    who: &AccountId32,
+    class_id: NonFungibleClassId,
+    attributes: Vec<runtime_types::pallet_support::Attribute>,
+  ) -> ResultOf<NonFungibleAssetId> {
+    let storage = finalbiome::storage().non_fungible_assets();
+    let mut details: NonFungibleDetails = self
+      .get(storage.classes(class_id).to_bytes())
+      .ok_or_else(|| error(NFA, "UnknownClass"))?;
+    let asset_id: NonFungibleAssetId = self
+      .get(storage.next_asset_id().to_bytes())
+      .unwrap_or(NonFungibleAssetId(0));
+
+    // the class attributes are copied to the instance
+    let prefix = map_prefix(storage.class_attributes_root().to_root_bytes(), &class_id);
+    let class_attributes: Vec<(AttributeKey, AttributeValue)> = self
+      .0
+      .range(prefix.clone()..)
+      .take_while(|(key, _)| key.starts_with(&prefix))
+      .filter_map(|(key, value)| {
+        // the attribute key follows the prefix and its Blake2_128 hash
+        let attr_key = AttributeKey::decode(&mut &key[prefix.len() + 16..]).ok()?;
+        let value = AttributeValue::decode(&mut &value[..]).ok()?;
+        Some((attr_key, value))
+      })
+      .collect();
+    for (key, value) in class_attributes {
+      self.put(storage.attributes(&asset_id, &key).to_bytes(), &value);
+    }
+    for attribute in attributes {
+      self.put(
+        storage.attributes(&asset_id, &attribute.key).to_bytes(),
+        &attribute.value,
+      );
+    }
+
+    let asset = NonFungibleAssetDetails {
+      owner: who.clone(),
+      locked: Locker::None,
+    };
+    self.put(storage.assets(class_id, &asset_id).to_bytes(), &asset);
+    self.put(storage.accounts(who, class_id, &asset_id).to_bytes(), &());
+    self.put(
+      storage.next_asset_id().to_bytes(),
+      &NonFungibleAssetId(asset_id.0 + 1),
+    );
+    details.instances += 1;
+    self.put(storage.classes(class_id).to_bytes(), &details);
+    Ok(asset_id)
+  }
+}
+
+/// Prefix of the map entries with the given first key hashed by Blake2_128Concat
+fn map_prefix<K: Encode>(mut root: Vec<u8>, key: &K) -> Vec<u8> {
+  subxt::storage::address::StorageMapKey::new(
+    key,
+    subxt::storage::address::StorageHasher::Blake2_128Concat,
+  )
+  .to_bytes(&mut root);
+  root
+}
@@ -0,0 +1,419 @@
+use serde::Serialize;
+use sp_runtime::AccountId32;
+use std::{collections::HashMap, fmt};
+
+use crate::{
+  bounded::BoundedVec,
+  finalbiome::runtime_types::{
+    pallet_fungible_assets::types::{CupFA, TopUppedFA},
+    pallet_support::{
+      characteristics::{bettor::BettorWinning, Characteristic},
+      Attribute,
+    },
+  },
+  game_spec::GameSpec,
+  AirDropAsset, AttributeKey, AttributesDetails, FungibleAssetDetails, FungibleAssetId,
+  NonFungibleClassId, NonFungibleDetails,
+};
+
+/// Account on whose behalf a call is signed
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StepSigner {
+  Organization,
+  Manager,
+  /// Player account with the given number
+  Player(usize),
+}
+
+/// A call submitted to the network during import.
+///
+/// Asset ids are the ids from the game spec, they are remapped to the ids of the created assets
+/// when the call is submitted. The only exceptions are the destroy calls, which refer to the assets
+/// existing in the network.
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "call", rename_all = "snake_case")]
+pub enum ImportCall {
+  CreateOrganization {
+    name: BoundedVec<u8>,
+  },
+  AddMember {
+    who: AccountId32,
+  },
+  CreateFa {
+    fa: FungibleAssetId,
+    name: BoundedVec<u8>,
+    top_upped: Option<TopUppedFA>,
+    cup_global: Option<CupFA>,
+    cup_local: Option<CupFA>,
+  },
+  CreateNfa {
+    class: NonFungibleClassId,
+    name: BoundedVec<u8>,
+  },
+  CreateAttribute {
+    class: NonFungibleClassId,
+    attribute: Attribute,
+  },
+  SetCharacteristic {
+    class: NonFungibleClassId,
+    characteristic: Characteristic,
+  },
+  SetOnboardingAssets {
+    assets: Vec<AirDropAsset>,
+  },
+  RemoveMember {
+    who: AccountId32,
+  },
+  RemoveAttribute {
+    class: NonFungibleClassId,
+    key: AttributeKey,
+  },
+  /// Destroys the FA with the given id in the network
+  DestroyFa {
+    asset_id: FungibleAssetId,
+  },
+  /// Destroys the NFA with the given id in the network
+  DestroyNfa {
+    class_id: NonFungibleClassId,
+  },
+  /// Onboards the player to the game, signed by the player
+  Onboarding {
+    player: AccountId32,
+  },
+}
+
+impl ImportCall {
+  /// Name of the pallet call
+  pub fn name(&self) -> &'static str {
+    match self {
+      ImportCall::CreateOrganization { .. } => "organization_identity.create_organization",
+      ImportCall::AddMember { .. } => "organization_identity.add_member",
+      ImportCall::CreateFa { .. } => "fungible_assets.create",
+      ImportCall::CreateNfa { .. } => "non_fungible_assets.create",
+      ImportCall::CreateAttribute { .. } => "non_fungible_assets.create_attribute",
+      ImportCall::SetCharacteristic { .. } => "non_fungible_assets.set_characteristic",
+      ImportCall::SetOnboardingAssets { .. } => "organization_identity.set_onboarding_assets",
+      ImportCall::RemoveMember { .. } => "organization_identity.remove_member",
+      ImportCall::RemoveAttribute { .. } => "non_fungible_assets.remove_attribute",
+      ImportCall::DestroyFa { .. } => "fungible_assets.destroy",
+      ImportCall::DestroyNfa { .. } => "non_fungible_assets.destroy",
+      ImportCall::Onboarding { .. } => "organization_identity.onboarding",
+    }
+  }
+
+  /// Returns true if the call can be dispatched in a batch with the neighbouring calls of the
+  /// same kind, the call doesn't create the assets whose ids are needed by the next calls
+  pub fn batchable(&self) -> bool {
+    matches!(
+      self,
+      ImportCall::AddMember { .. }
+        | ImportCall::CreateAttribute { .. }
+        | ImportCall::SetCharacteristic { .. }
+    )
+  }
+}
+
+/// A single step of the import
+#[derive(Serialize, Clone, Debug)]
+pub struct ImportStep {
+  pub signer: StepSigner,
+  #[serde(flatten)]
+  pub call: ImportCall,
+}
+
+/// The ordered list of calls which creates the game from the game spec
+#[derive(Serialize, Clone, Debug)]
+pub struct ImportPlan {
+  /// Account of the organization
+  pub organization: AccountId32,
+  /// Account of the manager
+  pub manager: AccountId32,
+  /// Ids of the spec FA which already exist in the network
+  pub fa_ids_map: Vec<(FungibleAssetId, FungibleAssetId)>,
+  /// Ids of the spec NFA which already exist in the network
+  pub nfa_ids_map: Vec<(NonFungibleClassId, NonFungibleClassId)>,
+  pub steps: Vec<ImportStep>,
+  /// Parts of the spec which cannot be recreated by the plan
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub notes: Vec<String>,
+}
+
+impl ImportPlan {
+  /// Builds the import plan for the game spec
+  pub fn new(game_spec: &GameSpec, organization: AccountId32, manager: AccountId32) -> ImportPlan {
+    let mut steps = vec![];
+    let mut push = |signer, call| steps.push(ImportStep { signer, call });
+
+    // 1. Create organization
+    push(
+      StepSigner::Organization,
+      ImportCall::CreateOrganization {
+        name: game_spec.organization_details.name.clone(),
+      },
+    );
+
+    // 2. Add members
+    // Also add the manager that is explicitly passed to the app (if the manager is not included in
+    // the specification)
+    let mut members = game_spec.organization_members.clone();
+    if !members.contains(&manager) {
+      members.push(manager.clone());
+    }
+    for who in members {
+      push(StepSigner::Organization, ImportCall::AddMember { who });
+    }
+
+    // 3. Create FA
+    for (fa, fa_details) in &game_spec.fa {
+      push(StepSigner::Manager, create_fa(*fa, fa_details));
+    }
+
+    // 4. Create NFA, then set their characteristics which may refer to any of them
+    for (class, nfa_details) in &game_spec.nfa {
+      for call in create_nfa(*class, nfa_details, &game_spec.attributes) {
+        push(StepSigner::Manager, call);
+      }
+    }
+    for (class, nfa_details) in &game_spec.nfa {
+      for call in set_characteristics(*class, nfa_details) {
+        push(StepSigner::Manager, call);
+      }
+    }
+
+    // LAST. Set onboarding assets
+    if let Some(onboarding) = game_spec.organization_details.onboarding_assets.clone() {
+      push(
+        StepSigner::Manager,
+        ImportCall::SetOnboardingAssets {
+          assets: onboarding.0,
+        },
+      );
+    }
+
+    ImportPlan {
+      organization,
+      manager,
+      fa_ids_map: vec![],
+      nfa_ids_map: vec![],
+      steps,
+      notes: unsupported_notes(game_spec),
+    }
+  }
+
+  /// Appends the onboarding of the given players to the game, the players which are already
+  /// onboarded are skipped.
+  ///
+  /// Onboarding goes last, so the players receive the onboarding assets.
+  pub fn onboard_players(&mut self, players: Vec<AccountId32>, onboarded: &[AccountId32]) {
+    for (idx, player) in players.into_iter().enumerate() {
+      if onboarded.contains(&player) {
+        continue;
+      }
+      self.steps.push(ImportStep {
+        signer: StepSigner::Player(idx),
+        call: ImportCall::Onboarding { player },
+      });
+    }
+  }
+}
+
+/// Returns the notes about the parts of the spec which the runtime doesn't allow to recreate
+pub(crate) fn unsupported_notes(game_spec: &GameSpec) -> Vec<String> {
+  let mut notes = vec![];
+  if let Some(balances) = game_spec.balances.as_ref().filter(|b| !b.is_empty()) {
+    notes.push(format!(
+      "{} FA balances are not recreated: the runtime has no call to issue or transfer FA",
+      balances.len()
+    ));
+  }
+  if let Some(instances) = game_spec.instances.as_ref().filter(|i| !i.is_empty()) {
+    notes.push(format!(
+      "{} NFA instances are not recreated: the runtime has no call to mint NFA",
+      instances.len()
+    ));
+  }
+  notes
+}
+
+/// Returns the call which creates the given FA
+pub(crate) fn create_fa(fa: FungibleAssetId, fa_details: &FungibleAssetDetails) -> ImportCall {
+  ImportCall::CreateFa {
+    fa,
+    name: fa_details.name.clone(),
+    top_upped: fa_details.top_upped.clone(),
+    cup_global: fa_details.cup_global.clone(),
+    cup_local: fa_details.cup_local.clone(),
+  }
+}
+
+/// Returns the calls which create the given NFA with its attributes.
+///
+/// The characteristics are set by [`set_characteristics`] after all classes are created, as a
+/// bettor may win the NFA of a class declared later in the spec.
+pub(crate) fn create_nfa(
+  class: NonFungibleClassId,
+  nfa_details: &NonFungibleDetails,
+  attributes: &AttributesDetails,
+) -> Vec<ImportCall> {
+  let mut calls = vec![ImportCall::CreateNfa {
+    class,
+    name: nfa_details.name.clone(),
+  }];
+  let attrs = attributes
+    .iter()
+    .filter(|(class_id, ..)| class_id == &class);
+  for (_class_id, key, value) in attrs.cloned() {
+    calls.push(ImportCall::CreateAttribute {
+      class,
+      attribute: Attribute { key, value },
+    });
+  }
+  calls
+}
+
+/// Returns the calls which set the characteristics of the given NFA
+pub(crate) fn set_characteristics(
+  class: NonFungibleClassId,
+  nfa_details: &NonFungibleDetails,
+) -> Vec<ImportCall> {
+  characteristics(nfa_details)
+    .into_iter()
+    .map(|characteristic| ImportCall::SetCharacteristic {
+      class,
+      characteristic,
+    })
+    .collect()
+}
+
+/// Replaces the spec ids of the assets in the characteristic with the ids in the network.
+///
+/// Returns `None` if some of the assets is not created yet.
+pub(crate) fn remap_characteristic(
+  mut characteristic: Characteristic,
+  fa_ids_map: &HashMap<FungibleAssetId, FungibleAssetId>,
+  nfa_ids_map: &HashMap<NonFungibleClassId, NonFungibleClassId>,
+) -> Option<Characteristic> {
+  match &mut characteristic {
+    Characteristic::Bettor(Some(bettor)) => {
+      for winning in &mut bettor.winnings.0 {
+        match winning {
+          BettorWinning::Fa(id, _) => *id = *fa_ids_map.get(id)?,
+          BettorWinning::Nfa(id) => *id = *nfa_ids_map.get(id)?,
+        }
+      }
+    },
+    Characteristic::Purchased(Some(purchased)) => {
+      for offer in &mut purchased.offers.0 {
+        offer.fa = *fa_ids_map.get(&offer.fa)?;
+      }
+    },
+    _ => (),
+  }
+  Some(characteristic)
+}
+
+/// Replaces the spec ids of the onboarding assets with the ids in the network.
+///
+/// Returns `None` if some of the assets is not created yet.
+pub(crate) fn remap_onboarding_assets(
+  assets: Vec<AirDropAsset>,
+  fa_ids_map: &HashMap<FungibleAssetId, FungibleAssetId>,
+  nfa_ids_map: &HashMap<NonFungibleClassId, NonFungibleClassId>,
+) -> Option<Vec<AirDropAsset>> {
+  assets
+    .into_iter()
+    .map(|asset| match asset {
+      AirDropAsset::Fa(id, bal) => Some(AirDropAsset::Fa(*fa_ids_map.get(&id)?, bal)),
+      AirDropAsset::Nfa(id, attrs) => Some(AirDropAsset::Nfa(*nfa_ids_map.get(&id)?, attrs)),
+    })
+    .collect()
+}
+
+/// Returns the characteristics of the NFA which should be set
+pub(crate) fn characteristics(nfa_details: &NonFungibleDetails) -> Vec<Characteristic> {
+  let mut characteristics = vec![];
+  if let Some(bettor) = &nfa_details.bettor {
+    characteristics.push(Characteristic::Bettor(Some(bettor.clone())));
+  }
+  if let Some(purchased) = &nfa_details.purchased {
+    characteristics.push(Characteristic::Purchased(Some(purchased.clone())));
+  }
+  characteristics
+}
+
+impl fmt::Display for ImportPlan {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(f, "Organization: {}", self.organization)?;
+    writeln!(f, "Manager:      {}", self.manager)?;
+    writeln!(f)?;
+    writeln!(f, "{:>4}  {:<12}  {:<44}  Arguments", "#", "Signer", "Call")?;
+    for (idx, step) in self.steps.iter().enumerate() {
+      let signer = match step.signer {
+        StepSigner::Organization => "organization".to_string(),
+        StepSigner::Manager => "manager".to_string(),
+        StepSigner::Player(idx) => format!("player {}", idx),
+      };
+      // render the arguments of the call without the tag, which is always serialized first.
+      // `serde_json::Value` is not used because it does not support u128 balances.
+      let call = serde_json::to_string(&step.call).map_err(|_| fmt::Error)?;
+      let args = match call.find(',') {
+        Some(pos) => format!("{{{}", &call[pos + 1..]),
+        None => "{}".to_string(),
+      };
+      writeln!(
+        f,
+        "{:>4}  {:<12}  {:<44}  {}",
+        idx + 1,
+        signer,
+        step.call.name(),
+        args
+      )?;
+    }
+    if !self.notes.is_empty() {
+      writeln!(f)?;
+    }
+    for note in &self.notes {
+      writeln!(f, "Note: {}", note)?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use super::*;
+  use crate::game_spec::tests::game_spec;
+
+  #[test]
+  fn characteristics_are_set_after_all_classes_are_created() {
+    // sword is won with the shield declared after it
+    let game_spec = game_spec(|content| {
+      content["nfa"][0][1]["bettor"] = json!({
+        "outcomes": [{ "name": "win", "probability": 100, "result": "Win" }],
+        "winnings": [{ "Nfa": "shield" }],
+        "rounds": 1,
+        "draw_outcome": "Keep",
+      });
+    });
+    let organization = game_spec.fa[0].1.owner.clone();
+    let manager = game_spec.organization_members[0].clone();
+    let plan = ImportPlan::new(&game_spec, organization, manager);
+    let position = |name: &str| {
+      plan
+        .steps
+        .iter()
+        .rposition(|step| step.call.name() == name)
+        .unwrap()
+    };
+    let first_characteristic = plan
+      .steps
+      .iter()
+      .position(|step| matches!(step.call, ImportCall::SetCharacteristic { .. }))
+      .unwrap();
+    assert!(position("non_fungible_assets.create") < first_characteristic);
+    assert!(position("non_fungible_assets.create_attribute") < first_characteristic);
+  }
+}
@@ -0,0 +1,444 @@
+use sp_runtime::AccountId32;
+use std::collections::HashMap;
+
+use crate::{
+  finalbiome::runtime_types::pallet_support::{characteristics::Characteristic, Attribute},
+  game_spec::GameSpec,
+  plan::{
+    characteristics, create_fa, create_nfa, remap_characteristic, remap_onboarding_assets,
+    set_characteristics, unsupported_notes, ImportCall, ImportPlan, ImportStep, StepSigner,
+  },
+};
+
+impl ImportPlan {
+  /// Builds the plan which brings the game existing in the network to the state of the game spec.
+  ///
+  /// Assets of the spec and of the network are matched by name. As the assets cannot be updated,
+  /// a changed FA is created again and the old one is destroyed.
+  pub fn reconcile(
+    game_spec: &GameSpec,
+    live_spec: &GameSpec,
+    organization: AccountId32,
+    manager: AccountId32,
+  ) -> ImportPlan {
+    let mut steps = vec![];
+    let mut push = |signer, call| steps.push(ImportStep { signer, call });
+    // calls which must be submitted after the assets are no longer referenced
+    let mut destroy_calls = vec![];
+
+    // 1. Add members, the manager always stays in the organization
+    let mut members = game_spec.organization_members.clone();
+    if !members.contains(&manager) {
+      members.push(manager.clone());
+    }
+    for who in members.iter() {
+      if !live_spec.organization_members.contains(who) {
+        push(
+          StepSigner::Organization,
+          ImportCall::AddMember { who: who.clone() },
+        );
+      }
+    }
+
+    // 2. Create FA which are not in the network or were changed
+    let mut fa_ids_map = HashMap::new();
+    for (fa, fa_details) in &game_spec.fa {
+      let live_fa = live_spec
+        .fa
+        .iter()
+        .find(|(_, live_details)| live_details.name == fa_details.name);
+      match live_fa {
+        Some((live_id, live_details))
+          if live_details.top_upped == fa_details.top_upped
+            && live_details.cup_global == fa_details.cup_global
+            && live_details.cup_local == fa_details.cup_local =>
+        {
+          fa_ids_map.insert(*fa, *live_id);
+        },
+        _ => push(StepSigner::Manager, create_fa(*fa, fa_details)),
+      }
+    }
+    for (live_id, _) in &live_spec.fa {
+      if !fa_ids_map.values().any(|id| id == live_id) {
+        destroy_calls.push(ImportCall::DestroyFa { asset_id: *live_id });
+      }
+    }
+
+    // 3. Create NFA which are not in the network and update the existing ones
+    let mut nfa_ids_map = HashMap::new();
+    for (class, nfa_details) in &game_spec.nfa {
+      let live_nfa = live_spec
+        .nfa
+        .iter()
+        .find(|(_, live_details)| live_details.name == nfa_details.name);
+      if let Some((live_id, _)) = live_nfa {
+        nfa_ids_map.insert(*class, *live_id);
+      }
+    }
+    for (live_id, _) in &live_spec.nfa {
+      if !nfa_ids_map.values().any(|id| id == live_id) {
+        // the attributes are removed first, like on teardown
+        for (class, key, _) in &live_spec.attributes {
+          if class == live_id {
+            destroy_calls.push(ImportCall::RemoveAttribute {
+              class: *class,
+              key: key.clone(),
+            });
+          }
+        }
+        destroy_calls.push(ImportCall::DestroyNfa { class_id: *live_id });
+      }
+    }
+    // characteristics may refer to the classes created later, so they are set after all of them
+    let mut characteristic_calls = vec![];
+    for (class, nfa_details) in &game_spec.nfa {
+      let Some(live_id) = nfa_ids_map.get(class) else {
+        for call in create_nfa(*class, nfa_details, &game_spec.attributes) {
+          push(StepSigner::Manager, call);
+        }
+        characteristic_calls.extend(set_characteristics(*class, nfa_details));
+        continue;
+      };
+      // the class is matched in the live spec, so it's always found
+      let Some(live_details) = live_spec.get_nfa(*live_id) else {
+        continue;
+      };
+
+      // 3.1 Attributes, there is no update call, so a changed attribute is created again
+      let attrs: Vec<_> = game_spec
+        .attributes
+        .iter()
+        .filter(|(class_id, ..)| class_id == class)
+        .collect();
+      let live_attrs: Vec<_> = live_spec
+        .attributes
+        .iter()
+        .filter(|(class_id, ..)| class_id == live_id)
+        .collect();
+      for (_, live_key, live_value) in &live_attrs {
+        let unchanged = attrs
+          .iter()
+          .any(|(_, key, value)| key == live_key && value == live_value);
+        if !unchanged {
+          push(
+            StepSigner::Manager,
+            ImportCall::RemoveAttribute {
+              class: *class,
+              key: live_key.clone(),
+            },
+          );
+        }
+      }
+      for (_, key, value) in &attrs {
+        let unchanged = live_attrs
+          .iter()
+          .any(|(_, live_key, live_value)| key == live_key && value == live_value);
+        if !unchanged {
+          push(
+            StepSigner::Manager,
+            ImportCall::CreateAttribute {
+              class: *class,
+              attribute: Attribute {
+                key: key.clone(),
+                value: value.clone(),
+              },
+            },
+          );
+        }
+      }
+
+      // 3.2 Characteristics
+      for characteristic in characteristics(nfa_details) {
+        let live_characteristic = match characteristic {
+          Characteristic::Bettor(_) => Characteristic::Bettor(live_details.bettor.clone()),
+          Characteristic::Purchased(_) => Characteristic::Purchased(live_details.purchased.clone()),
+        };
+        // the characteristic which refers to the new assets is always changed
+        let remapped = remap_characteristic(characteristic.clone(), &fa_ids_map, &nfa_ids_map);
+        if remapped.as_ref() != Some(&live_characteristic) {
+          characteristic_calls.push(ImportCall::SetCharacteristic {
+            class: *class,
+            characteristic,
+          });
+        }
+      }
+      if nfa_details.bettor.is_none() && live_details.bettor.is_some() {
+        characteristic_calls.push(ImportCall::SetCharacteristic {
+          class: *class,
+          characteristic: Characteristic::Bettor(None),
+        });
+      }
+      if nfa_details.purchased.is_none() && live_details.purchased.is_some() {
+        characteristic_calls.push(ImportCall::SetCharacteristic {
+          class: *class,
+          characteristic: Characteristic::Purchased(None),
+        });
+      }
+    }
+    for call in characteristic_calls {
+      push(StepSigner::Manager, call);
+    }
+
+    // 4. Onboarding assets
+    let onboarding = game_spec
+      .organization_details
+      .onboarding_assets
+      .clone()
+      .map(|assets| assets.0)
+      .unwrap_or_default();
+    let live_onboarding = live_spec
+      .organization_details
+      .onboarding_assets
+      .clone()
+      .map(|assets| assets.0)
+      .unwrap_or_default();
+    let remapped = remap_onboarding_assets(onboarding.clone(), &fa_ids_map, &nfa_ids_map);
+    if remapped != Some(live_onboarding) {
+      push(
+        StepSigner::Manager,
+        ImportCall::SetOnboardingAssets { assets: onboarding },
+      );
+    }
+
+    // 5. Destroy the assets which are not in the spec
+    for call in destroy_calls {
+      push(StepSigner::Manager, call);
+    }
+
+    // 6. Remove members which are not in the spec
+    for who in &live_spec.organization_members {
+      if !members.contains(who) {
+        push(
+          StepSigner::Organization,
+          ImportCall::RemoveMember { who: who.clone() },
+        );
+      }
+    }
+
+    let mut fa_ids_map: Vec<_> = fa_ids_map.into_iter().collect();
+    fa_ids_map.sort_by_key(|(id, _)| id.0);
+    let mut nfa_ids_map: Vec<_> = nfa_ids_map.into_iter().collect();
+    nfa_ids_map.sort_by_key(|(id, _)| id.0);
+
+    ImportPlan {
+      organization,
+      manager,
+      fa_ids_map,
+      nfa_ids_map,
+      steps,
+      notes: unsupported_notes(game_spec),
+    }
+  }
+
+  /// Builds the plan which creates the items of the game spec missing in the game existing in
+  /// the network.
+  ///
+  /// Unlike [`ImportPlan::reconcile`] nothing is removed or created again: members and assets are
+  /// matched by name, attributes by key. Existing items which differ from the spec are kept as is
+  /// and reported in the notes.
+  pub fn complete(
+    game_spec: &GameSpec,
+    live_spec: &GameSpec,
+    organization: AccountId32,
+    manager: AccountId32,
+  ) -> ImportPlan {
+    let mut steps = vec![];
+    let mut push = |signer, call| steps.push(ImportStep { signer, call });
+    let mut notes = vec![format!(
+      "Organization {} already exists, only the missing items of the spec are created",
+      organization
+    )];
+    notes.extend(unsupported_notes(game_spec));
+    let differs = |item: &str, name: &[u8]| {
+      format!(
+        "{} {:?} differs from the spec, use apply to update it",
+        item,
+        String::from_utf8_lossy(name)
+      )
+    };
+
+    // 1. Add members
+    let mut members = game_spec.organization_members.clone();
+    if !members.contains(&manager) {
+      members.push(manager.clone());
+    }
+    for who in members {
+      if !live_spec.organization_members.contains(&who) {
+        push(StepSigner::Organization, ImportCall::AddMember { who });
+      }
+    }
+
+    // 2. Create FA which are not in the network
+    let mut fa_ids_map = HashMap::new();
+    for (fa, fa_details) in &game_spec.fa {
+      let live_fa = live_spec
+        .fa
+        .iter()
+        .find(|(_, live_details)| live_details.name == fa_details.name);
+      match live_fa {
+        Some((live_id, live_details)) => {
+          if live_details.top_upped != fa_details.top_upped
+            || live_details.cup_global != fa_details.cup_global
+            || live_details.cup_local != fa_details.cup_local
+          {
+            notes.push(differs("FA", &fa_details.name.0));
+          }
+          fa_ids_map.insert(*fa, *live_id);
+        },
+        None => push(StepSigner::Manager, create_fa(*fa, fa_details)),
+      }
+    }
+
+    // 3. Create NFA which are not in the network and their missing attributes and characteristics
+    let mut nfa_ids_map = HashMap::new();
+    for (class, nfa_details) in &game_spec.nfa {
+      let live_nfa = live_spec
+        .nfa
+        .iter()
+        .find(|(_, live_details)| live_details.name == nfa_details.name);
+      if let Some((live_id, _)) = live_nfa {
+        nfa_ids_map.insert(*class, *live_id);
+      }
+    }
+    // characteristics may refer to the classes created later, so they are set after all of them
+    let mut characteristic_calls = vec![];
+    for (class, nfa_details) in &game_spec.nfa {
+      let Some(live_id) = nfa_ids_map.get(class) else {
+        for call in create_nfa(*class, nfa_details, &game_spec.attributes) {
+          push(StepSigner::Manager, call);
+        }
+        characteristic_calls.extend(set_characteristics(*class, nfa_details));
+        continue;
+      };
+      // the class is matched in the live spec, so it's always found
+      let Some(live_details) = live_spec.get_nfa(*live_id) else {
+        continue;
+      };
+
+      // 3.1 Attributes
+      let attrs = game_spec
+        .attributes
+        .iter()
+        .filter(|(class_id, ..)| class_id == class);
+      for (_, key, value) in attrs {
+        let live_attr = live_spec
+          .attributes
+          .iter()
+          .find(|(class_id, live_key, _)| class_id == live_id && live_key == key);
+        match live_attr {
+          Some((.., live_value)) if live_value != value => {
+            notes.push(differs("Attribute", &key.0));
+          },
+          Some(_) => {},
+          None => push(
+            StepSigner::Manager,
+            ImportCall::CreateAttribute {
+              class: *class,
+              attribute: Attribute {
+                key: key.clone(),
+                value: value.clone(),
+              },
+            },
+          ),
+        }
+      }
+
+      // 3.2 Characteristics
+      for characteristic in characteristics(nfa_details) {
+        let live_characteristic = match characteristic {
+          Characteristic::Bettor(_) => live_details
+            .bettor
+            .clone()
+            .map(|bettor| Characteristic::Bettor(Some(bettor))),
+          Characteristic::Purchased(_) => live_details
+            .purchased
+            .clone()
+            .map(|purchased| Characteristic::Purchased(Some(purchased))),
+        };
+        let Some(live_characteristic) = live_characteristic else {
+          characteristic_calls.push(ImportCall::SetCharacteristic {
+            class: *class,
+            characteristic,
+          });
+          continue;
+        };
+        let remapped = remap_characteristic(characteristic, &fa_ids_map, &nfa_ids_map);
+        if remapped.as_ref() != Some(&live_characteristic) {
+          notes.push(differs("Characteristic of NFA", &nfa_details.name.0));
+        }
+      }
+    }
+    for call in characteristic_calls {
+      push(StepSigner::Manager, call);
+    }
+
+    // 4. Onboarding assets
+    let onboarding = game_spec
+      .organization_details
+      .onboarding_assets
+      .clone()
+      .map(|assets| assets.0)
+      .unwrap_or_default();
+    let live_onboarding = live_spec
+      .organization_details
+      .onboarding_assets
+      .clone()
+      .map(|assets| assets.0)
+      .unwrap_or_default();
+    if live_onboarding.is_empty() && !onboarding.is_empty() {
+      push(
+        StepSigner::Manager,
+        ImportCall::SetOnboardingAssets { assets: onboarding },
+      );
+    } else if remap_onboarding_assets(onboarding, &fa_ids_map, &nfa_ids_map)
+      != Some(live_onboarding)
+    {
+      notes.push("Onboarding assets differ from the spec, use apply to update them".to_string());
+    }
+
+    let mut fa_ids_map: Vec<_> = fa_ids_map.into_iter().collect();
+    fa_ids_map.sort_by_key(|(id, _)| id.0);
+    let mut nfa_ids_map: Vec<_> = nfa_ids_map.into_iter().collect();
+    nfa_ids_map.sort_by_key(|(id, _)| id.0);
+
+    ImportPlan {
+      organization,
+      manager,
+      fa_ids_map,
+      nfa_ids_map,
+      steps,
+      notes,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use super::*;
+  use crate::game_spec::tests::game_spec;
+
+  #[test]
+  fn attributes_of_removed_class_are_removed_before_it() {
+    let live_spec = game_spec(|_| {});
+    let game_spec = game_spec(|content| {
+      content["nfa"] = json!([content["nfa"][1]]);
+      content["attributes"] = json!([]);
+      content["organization_details"]["onboarding_assets"] = json!([{ "Fa": ["gold", 5] }]);
+    });
+    let organization = live_spec.fa[0].1.owner.clone();
+    let manager = live_spec.organization_members[0].clone();
+    let plan = ImportPlan::reconcile(&game_spec, &live_spec, organization, manager);
+    let names: Vec<_> = plan.steps.iter().map(|step| step.call.name()).collect();
+    assert_eq!(
+      names,
+      vec![
+        "organization_identity.set_onboarding_assets",
+        "non_fungible_assets.remove_attribute",
+        "non_fungible_assets.remove_attribute",
+        "non_fungible_assets.destroy",
+      ]
+    );
+  }
+}
@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+use sp_core::hexdisplay::HexDisplay;
+use sp_runtime::AccountId32;
+use std::{fs::File, path::Path};
+
+use crate::{
+  journal::ImportJournal, FungibleAssetId, ImpexError, NonFungibleClassId, ResultOf, SubmittedCall,
+};
+
+/// An event emitted by the submitted call
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EventReport {
+  pub pallet: String,
+  pub variant: String,
+  /// Decoded fields of the event
+  pub fields: String,
+}
+
+/// Result of the submitted call
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct StepReport {
+  /// Name of the pallet call
+  pub call: String,
+  /// Hash of the block in which the call was included
+  pub block_hash: String,
+  /// Hash of the extrinsic
+  pub extrinsic_hash: String,
+  pub events: Vec<EventReport>,
+}
+
+impl StepReport {
+  /// Builds the report from the events of the submitted extrinsic
+  pub(crate) fn new(call: &str, submitted: &SubmittedCall) -> StepReport {
+    StepReport {
+      call: call.to_string(),
+      block_hash: format!("0x{}", HexDisplay::from(&submitted.block_hash.as_ref())),
+      extrinsic_hash: format!("0x{}", HexDisplay::from(&submitted.extrinsic_hash.as_ref())),
+      events: submitted.reports.clone(),
+    }
+  }
+}
+
+/// Result of the import of the game spec
+#[derive(Serialize, Clone, Debug)]
+pub struct ImportReport {
+  /// Account of the created organization
+  pub organization: AccountId32,
+  /// Ids of the FA in the game spec and of the created FA
+  pub fa_ids_map: Vec<(FungibleAssetId, FungibleAssetId)>,
+  /// Ids of the NFA in the game spec and of the created NFA
+  pub nfa_ids_map: Vec<(NonFungibleClassId, NonFungibleClassId)>,
+  /// Submitted calls
+  pub steps: Vec<StepReport>,
+}
+
+impl ImportReport {
+  /// Builds the report from the journal of the completed import
+  pub(crate) fn new(organization: AccountId32, journal: &ImportJournal) -> ImportReport {
+    ImportReport {
+      organization,
+      fa_ids_map: journal.fa_ids_map.clone(),
+      nfa_ids_map: journal.nfa_ids_map.clone(),
+      steps: journal.steps.clone(),
+    }
+  }
+
+  /// Writes the report to file as JSON
+  pub fn save<P: AsRef<Path>>(&self, path: P) -> ResultOf<()> {
+    let f = File::create(&path).map_err(|e| ImpexError::file(path.as_ref(), e))?;
+    serde_json::to_writer_pretty(f, self)?;
+    Ok(())
+  }
+}
@@ -0,0 +1,264 @@
+use parity_scale_codec::Decode;
+use serde_json::{json, Value};
+
+use crate::{backend::bundled_metadata, migration::SPEC_FORMAT_VERSION, ResultOf};
+
+/// Length limits of the runtime, taken from the constants of the pallets.
+///
+/// Only the limits in the metadata constants are put into the schema. The NFA class name, the
+/// bettor outcome names and the attribute keys and text values are bounded by the types of
+/// `pallet_support` (`DefaultStringLimit`, `AttributeKey` and `AttributeValueStringLimit`), and
+/// `NonFungibleAssets` has no constants for them. They are left without `maxLength` rather than
+/// with the values copied from the runtime sources, which could silently go out of date, and are
+/// named in the descriptions instead.
+struct Limits {
+  organization_name: u32,
+  organization_members: u8,
+  fa_name: u32,
+}
+
+impl Limits {
+  fn from_metadata() -> ResultOf<Limits> {
+    let metadata = bundled_metadata()?;
+    let constant = |pallet: &str, name: &str| -> ResultOf<Vec<u8>> {
+      Ok(metadata.pallet(pallet)?.constant(name)?.value.clone())
+    };
+    Ok(Limits {
+      organization_name: u32::decode(&mut &*constant("OrganizationIdentity", "StringLimit")?)?,
+      organization_members: u8::decode(&mut &*constant("OrganizationIdentity", "MaxMembers")?)?,
+      fa_name: u32::decode(&mut &*constant("FungibleAssets", "NameLimit")?)?,
+    })
+  }
+}
+
+fn reference(name: &str) -> Value {
+  json!({ "$ref": format!("#/definitions/{}", name) })
+}
+
+fn nullable(name: &str) -> Value {
+  json!({ "oneOf": [{ "type": "null" }, reference(name)] })
+}
+
+fn array_of(items: Value) -> Value {
+  json!({ "type": "array", "items": items })
+}
+
+/// Fixed length array, the way tuples are serialized
+fn tuple(items: Vec<Value>) -> Value {
+  let len = items.len();
+  json!({ "type": "array", "items": items, "minItems": len, "maxItems": len })
+}
+
+/// Single key object, the way enum variants with data are serialized
+fn variant(name: &str, value: Value) -> Value {
+  json!({
+    "type": "object",
+    "properties": { name: value },
+    "required": [name],
+    "additionalProperties": false,
+  })
+}
+
+fn object(properties: Value) -> Value {
+  let required: Vec<&String> = properties
+    .as_object()
+    .map(|props| props.keys().collect())
+    .unwrap_or_default();
+  json!({ "type": "object", "properties": properties, "required": required })
+}
+
+/// Bytes written as a string or, by the older versions, as an array of numbers
+fn bytes(max_len: Option<u32>) -> Value {
+  let mut string = json!({
+    "type": "string",
+    "description": "UTF-8 string or 0x-prefixed hex of the bytes",
+  });
+  let mut array = array_of(json!({ "type": "integer", "minimum": 0, "maximum": 255 }));
+  if let Some(max_len) = max_len {
+    string["description"] = json!(format!(
+      "UTF-8 string or 0x-prefixed hex of the bytes, at most {} bytes",
+      max_len
+    ));
+    array["maxItems"] = json!(max_len);
+  }
+  json!({ "oneOf": [string, array] })
+}
+
+/// Bytes bounded by the type of the runtime, whose limit is not in the metadata
+fn bounded_bytes(bound: &str) -> Value {
+  let mut schema = bytes(None);
+  schema["oneOf"][0]["description"] = json!(format!(
+    "UTF-8 string or 0x-prefixed hex of the bytes, bounded by {} of the runtime",
+    bound
+  ));
+  schema
+}
+
+/// Numeric id or symbolic handle of the asset declared in the spec
+fn asset_id(kind: &str) -> Value {
+  json!({ "oneOf": [
+    { "type": "integer", "minimum": 0, "maximum": u32::MAX },
+    { "type": "string", "description": format!("Handle of the {} declared in the spec", kind) },
+  ]})
+}
+
+fn definitions(limits: &Limits) -> Value {
+  let u32_schema = json!({ "type": "integer", "minimum": 0, "maximum": u32::MAX });
+  json!({
+    "AccountId": { "type": "string", "description": "Account address in SS58 format" },
+    "U32": u32_schema,
+    "Balance": { "type": "integer", "minimum": 0 },
+    "FungibleAssetId": asset_id("FA"),
+    "NonFungibleClassId": asset_id("NFA class"),
+    "String": bounded_bytes("DefaultStringLimit"),
+    "AttributeKey": bounded_bytes("AttributeKey"),
+    "TopUppedFA": object(json!({ "speed": reference("Balance") })),
+    "CupFA": object(json!({ "amount": reference("Balance") })),
+    "AssetDetails": object(json!({
+      "owner": reference("AccountId"),
+      "supply": reference("Balance"),
+      "accounts": reference("U32"),
+      "references": reference("U32"),
+      "name": bytes(Some(limits.fa_name)),
+      "top_upped": nullable("TopUppedFA"),
+      "cup_global": nullable("CupFA"),
+      "cup_local": nullable("CupFA"),
+    })),
+    "NumberAttribute": object(json!({
+      "number_value": reference("U32"),
+      "number_max": { "oneOf": [{ "type": "null" }, reference("U32")] },
+    })),
+    "AttributeValue": { "oneOf": [
+      variant("Number", reference("NumberAttribute")),
+      variant("Text", bounded_bytes("AttributeValueStringLimit")),
+    ]},
+    "Attribute": object(json!({
+      "key": reference("AttributeKey"),
+      "value": reference("AttributeValue"),
+    })),
+    "AirDropAsset": { "oneOf": [
+      variant("Fa", tuple(vec![reference("FungibleAssetId"), reference("Balance")])),
+      variant("Nfa", tuple(vec![reference("NonFungibleClassId"), array_of(reference("Attribute"))])),
+    ]},
+    "OrganizationDetails": object(json!({
+      "name": bytes(Some(limits.organization_name)),
+      "onboarding_assets": { "oneOf": [{ "type": "null" }, array_of(reference("AirDropAsset"))] },
+    })),
+    "OutcomeResult": { "enum": ["Win", "Lose", "Draw"] },
+    "DrawOutcomeResult": { "enum": ["Win", "Lose", "Keep"] },
+    "BettorOutcome": object(json!({
+      "name": reference("String"),
+      "probability": reference("U32"),
+      "result": reference("OutcomeResult"),
+    })),
+    "BettorWinning": { "oneOf": [
+      variant("Fa", tuple(vec![reference("FungibleAssetId"), reference("Balance")])),
+      variant("Nfa", reference("NonFungibleClassId")),
+    ]},
+    "Bettor": object(json!({
+      "outcomes": array_of(reference("BettorOutcome")),
+      "winnings": array_of(reference("BettorWinning")),
+      "rounds": reference("U32"),
+      "draw_outcome": reference("DrawOutcomeResult"),
+    })),
+    "Offer": object(json!({
+      "fa": reference("FungibleAssetId"),
+      "price": reference("Balance"),
+      "attributes": array_of(reference("Attribute")),
+    })),
+    "Purchased": object(json!({ "offers": array_of(reference("Offer")) })),
+    "NonFungibleInstance": object(json!({
+      "class": reference("NonFungibleClassId"),
+      "asset": reference("U32"),
+      "owner": reference("AccountId"),
+      "locked": { "type": "boolean" },
+      "attributes": array_of(tuple(vec![reference("AttributeKey"), reference("AttributeValue")])),
+    })),
+    "ClassDetails": object(json!({
+      "owner": reference("AccountId"),
+      "instances": reference("U32"),
+      "attributes": reference("U32"),
+      "name": reference("String"),
+      "bettor": nullable("Bettor"),
+      "purchased": nullable("Purchased"),
+    })),
+  })
+}
+
+/// Returns the JSON Schema of the game spec file.
+///
+/// Length limits are taken from the constants of the runtime metadata the utility is built for.
+pub fn game_spec_schema() -> ResultOf<Value> {
+  let limits = Limits::from_metadata()?;
+  let mut members = array_of(reference("AccountId"));
+  members["maxItems"] = json!(limits.organization_members);
+
+  let mut schema = object(json!({
+    "spec_format_version": { "const": SPEC_FORMAT_VERSION },
+    "node_version": { "type": "string", "description": "Version of the node" },
+    "hash": {
+      "type": "string",
+      "description": "Hash of the network block when the game spec was exported",
+    },
+    "organization_details": reference("OrganizationDetails"),
+    "organization_members": members,
+    "fa": array_of(tuple(vec![reference("FungibleAssetId"), reference("AssetDetails")])),
+    "nfa": array_of(tuple(vec![reference("NonFungibleClassId"), reference("ClassDetails")])),
+    "attributes": array_of(tuple(vec![
+      reference("NonFungibleClassId"),
+      reference("AttributeKey"),
+      reference("AttributeValue"),
+    ])),
+  }));
+  // users, their balances and NFA instances are exported only on request
+  schema["properties"]["users"] = array_of(reference("AccountId"));
+  schema["properties"]["balances"] = array_of(tuple(vec![
+    reference("AccountId"),
+    reference("FungibleAssetId"),
+    reference("Balance"),
+  ]));
+  schema["properties"]["instances"] = array_of(reference("NonFungibleInstance"));
+  // ids of the assets declared by handles, written on export
+  let ids = json!({ "type": "object", "additionalProperties": reference("U32") });
+  schema["properties"]["asset_ids"] = json!({
+    "type": "object",
+    "properties": { "fa": ids, "nfa": ids },
+    "description": "Ids of the assets by the handles which declare them",
+  });
+  schema["$schema"] = json!("http://json-schema.org/draft-07/schema#");
+  schema["title"] = json!("FinalBiome game spec");
+  schema["definitions"] = definitions(&limits);
+  Ok(schema)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn published_schema_is_up_to_date() {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("game_spec.schema.json");
+    let published: Value = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+    assert_eq!(
+      published,
+      game_spec_schema().unwrap(),
+      "game_spec.schema.json is out of date, regenerate it with `cargo run schema -o ./game_spec.schema.json`"
+    );
+  }
+
+  #[test]
+  fn nfa_limits_are_not_in_metadata() {
+    // once the runtime exposes the limits of pallet_support, they become `maxLength` in the schema
+    let metadata = bundled_metadata().unwrap();
+    let nfa = metadata
+      .runtime_metadata()
+      .pallets
+      .iter()
+      .find(|pallet| pallet.name == "NonFungibleAssets")
+      .unwrap();
+    assert!(
+      nfa.constants.is_empty(),
+      "NonFungibleAssets exposes its limits now, add them to Limits"
+    );
+  }
+}
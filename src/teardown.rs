@@ -0,0 +1,102 @@
+use sp_runtime::AccountId32;
+
+use crate::{
+  game_spec::GameSpec,
+  plan::{ImportCall, ImportPlan, ImportStep, StepSigner},
+};
+
+impl ImportPlan {
+  /// Builds the plan which removes the items of the game existing in the network.
+  ///
+  /// The onboarding assets are cleared first, then the attributes are removed and the NFA and FA
+  /// are destroyed. Members are removed at the end, the manager is the last one, as the calls are
+  /// signed by the organization. If `remove_members` is false, the members are kept and reported
+  /// in the notes.
+  pub fn teardown(
+    live_spec: &GameSpec,
+    organization: AccountId32,
+    manager: AccountId32,
+    remove_members: bool,
+  ) -> ImportPlan {
+    let mut steps = vec![];
+    let mut push = |signer, call| steps.push(ImportStep { signer, call });
+    let mut notes = vec![];
+
+    // 1. Onboarding assets refer to the destroyed assets
+    let onboarding = live_spec
+      .organization_details
+      .onboarding_assets
+      .as_ref()
+      .is_some_and(|assets| !assets.0.is_empty());
+    if onboarding {
+      push(
+        StepSigner::Manager,
+        ImportCall::SetOnboardingAssets { assets: vec![] },
+      );
+    }
+
+    // 2. Attributes of the NFA
+    for (class, key, _) in &live_spec.attributes {
+      push(
+        StepSigner::Manager,
+        ImportCall::RemoveAttribute {
+          class: *class,
+          key: key.clone(),
+        },
+      );
+    }
+
+    // 3. NFA may refer to the FA in their characteristics, so they are destroyed first
+    for (class_id, _) in &live_spec.nfa {
+      push(
+        StepSigner::Manager,
+        ImportCall::DestroyNfa {
+          class_id: *class_id,
+        },
+      );
+    }
+    for (asset_id, _) in &live_spec.fa {
+      push(
+        StepSigner::Manager,
+        ImportCall::DestroyFa {
+          asset_id: *asset_id,
+        },
+      );
+    }
+
+    // 4. Members, the manager signs the calls above, so it's removed last
+    let mut members: Vec<_> = live_spec
+      .organization_members
+      .iter()
+      .filter(|who| **who != manager)
+      .collect();
+    if live_spec.organization_members.contains(&manager) {
+      members.push(&manager);
+    }
+    if remove_members {
+      for who in members {
+        push(
+          StepSigner::Organization,
+          ImportCall::RemoveMember { who: who.clone() },
+        );
+      }
+    } else if !members.is_empty() {
+      notes.push(format!(
+        "{} members are kept, the organization seed is needed to remove them",
+        members.len()
+      ));
+    }
+
+    // the attributes are removed by the ids of the network
+    let nfa_ids_map = live_spec.nfa.iter().map(|(id, _)| (*id, *id)).collect();
+
+    ImportPlan {
+      organization,
+      manager,
+      fa_ids_map: vec![],
+      nfa_ids_map,
+      steps,
+      notes,
+    }
+  }
+}
@@ -1,36 +1,34 @@
-use sp_core::storage::StorageKey;
-use subxt::{
-  blocks::ExtrinsicEvents,
-  tx::{PairSigner, StaticTxPayload},
-  OnlineClient,
-};
+use futures::{stream, StreamExt, TryStreamExt};
+use parity_scale_codec::Decode;
+use sp_core::{hexdisplay::HexDisplay, storage::StorageKey, H256};
+use sp_runtime::AccountId32;
 
-use crate::ResultOf;
+use crate::{ChainBackend, FetchOptions, ImpexError, ResultOf};
 
 /// Iterates over all keys in a map by prefix key
-pub(crate) struct AllKeyIter<'a, T>
+pub(crate) struct AllKeyIter<'a, B>
 where
-  T: subxt::Config,
+  B: ChainBackend + ?Sized,
 {
-  api: &'a OnlineClient<T>,
+  api: &'a B,
   query_key: Vec<u8>,
   page_size: u32,
-  block_hash: T::Hash,
+  block_hash: H256,
   start_key: Option<StorageKey>,
   buffer: Vec<StorageKey>,
 }
 
-impl<'a, T> AllKeyIter<'a, T>
+impl<'a, B> AllKeyIter<'a, B>
 where
-  T: subxt::Config,
+  B: ChainBackend + ?Sized,
 {
   /// Create new iterator
   pub fn new(
-    api: &OnlineClient<T>,
+    api: &'a B,
     query_key: Vec<u8>,
-    block_hash: T::Hash,
+    block_hash: H256,
     page_size: u32,
-  ) -> AllKeyIter<T> {
+  ) -> AllKeyIter<'a, B> {
     AllKeyIter {
       api,
       query_key,
@@ -47,16 +45,18 @@ where
         return Ok(Some(k));
       } else {
         let start_key = self.start_key.take();
-        let mut keys = self
+        let mut keys: Vec<StorageKey> = self
           .api
-          .storage()
           .fetch_keys(
             &self.query_key,
             self.page_size,
             start_key.as_ref().map(|k| &*k.0),
-            Some(self.block_hash),
+            self.block_hash,
           )
-          .await?;
+          .await?
+          .into_iter()
+          .map(StorageKey)
+          .collect();
 
         if keys.is_empty() {
           return Ok(None);
@@ -69,30 +69,110 @@ where
   }
 }
 
-/// Submit Tx payload with default settings.
-pub(crate) async fn submit_default<T, C, P>(
-  api: &OnlineClient<T>,
-  payload: &StaticTxPayload<C>,
-  signer: &PairSigner<T, P>,
-) -> ResultOf<ExtrinsicEvents<T>>
+/// Fetches the values by the storage keys at the given block.
+///
+/// Keys are requested in chunks of the page size, several chunks at once.
+/// Returns the values in the order of the keys, `None` for the keys without value.
+pub(crate) async fn fetch_values<B, V>(
+  api: &B,
+  keys: Vec<Vec<u8>>,
+  block_hash: H256,
+  options: &FetchOptions,
+) -> ResultOf<Vec<Option<V>>>
 where
-  T: subxt::Config,
-  P: sp_core::Pair,
-  C: parity_scale_codec::Encode,
-  <<T as subxt::Config>::ExtrinsicParams as subxt::tx::ExtrinsicParams<
-    <T as subxt::Config>::Index,
-    <T as subxt::Config>::Hash,
-  >>::OtherParams: std::default::Default,
-  <T as subxt::Config>::Address: std::convert::From<<T as subxt::Config>::AccountId>,
-  <T as subxt::Config>::Signature: std::convert::From<<P as sp_core::Pair>::Signature>,
+  B: ChainBackend + ?Sized,
+  V: Decode,
 {
-  let events = api
-    .tx()
-    .sign_and_submit_then_watch_default(payload, signer)
-    .await?
-    .wait_for_in_block()
-    .await?
-    .wait_for_success()
+  let chunks: Vec<Vec<Vec<u8>>> = keys
+    .chunks(options.page_size.max(1) as usize)
+    .map(|chunk| chunk.to_vec())
+    .collect();
+  let values: Vec<Vec<Option<V>>> = stream::iter(chunks)
+    .map(|chunk| async move {
+      let mut values = vec![];
+      for value in api.fetch_values(&chunk, block_hash).await? {
+        let value = match value {
+          Some(value) => Some(V::decode(&mut &value[..])?),
+          None => None,
+        };
+        values.push(value);
+      }
+      ResultOf::Ok(values)
+    })
+    .buffered(options.parallelism.max(1))
+    .try_collect()
     .await?;
-  Ok(events)
+  Ok(values.into_iter().flatten().collect())
+}
+
+/// Fetches the value by the storage key at the given block
+pub(crate) async fn fetch_value<B, V>(
+  api: &B,
+  key: Vec<u8>,
+  block_hash: H256,
+) -> ResultOf<Option<V>>
+where
+  B: ChainBackend + ?Sized,
+  V: Decode,
+{
+  match api.fetch_values(&[key], block_hash).await?.pop().flatten() {
+    Some(value) => Ok(Some(V::decode(&mut &value[..])?)),
+    None => Ok(None),
+  }
+}
+
+/// Returns the last `len` bytes of the storage key, which encode its last map key
+pub(crate) fn key_suffix(key: &[u8], len: usize) -> ResultOf<&[u8]> {
+  key
+    .len()
+    .checked_sub(len)
+    .map(|start| &key[start..])
+    .ok_or_else(|| {
+      ImpexError::Decode(format!(
+        "Storage key 0x{} is shorter than {} bytes",
+        HexDisplay::from(&key),
+        len
+      ))
+    })
+}
+
+/// Returns the bytes of the storage key after its first `offset` bytes, which encode its last map key
+pub(crate) fn key_after(key: &[u8], offset: usize) -> ResultOf<&[u8]> {
+  key.get(offset..).ok_or_else(|| {
+    ImpexError::Decode(format!(
+      "Storage key 0x{} is shorter than {} bytes",
+      HexDisplay::from(&key),
+      offset
+    ))
+  })
+}
+
+/// Decodes the account id from the last 32 bytes of the storage key
+pub(crate) fn account_from_key(key: &[u8]) -> ResultOf<AccountId32> {
+  let account: [u8; 32] = key_suffix(key, 32)?
+    .try_into()
+    .map_err(|_| ImpexError::Decode("Account id is not 32 bytes long".to_string()))?;
+  Ok(AccountId32::new(account))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn account_is_decoded_from_key_end() {
+    let mut key = vec![7; 16];
+    key.extend([1; 32]);
+    assert_eq!(account_from_key(&key).unwrap(), AccountId32::new([1; 32]));
+    assert_eq!(key_suffix(&key, 4).unwrap(), &[1; 4]);
+  }
+
+  #[test]
+  fn short_key_is_not_decoded() {
+    let result = account_from_key(&[1; 20]);
+    assert!(matches!(result, Err(ImpexError::Decode(_))), "{:?}", result);
+    let result = key_after(&[1; 20], 32);
+    assert!(matches!(result, Err(ImpexError::Decode(_))), "{:?}", result);
+    assert_eq!(key_after(&[1; 20], 16).unwrap(), &[1; 4]);
+  }
 }
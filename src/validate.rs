@@ -0,0 +1,516 @@
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use serde_path_to_error::Segment;
+use std::{
+  collections::{HashMap, HashSet},
+  fmt,
+  fmt::Write,
+};
+
+use crate::{
+  finalbiome::runtime_types::pallet_support::characteristics::bettor::BettorWinning,
+  format::SpecFormat, game_spec::GameSpec, handle::Handles, migration::SPEC_FORMAT_VERSION,
+  AirDropAsset, BoundedVec, FungibleAssetId, NonFungibleClassId, OrganizationDetails,
+};
+
+/// A single problem found in the game spec.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct ValidationProblem {
+  /// JSON path to the invalid value, e.g. `$.fa[2][0]`
+  pub path: String,
+  /// Human readable description of the problem
+  pub message: String,
+}
+
+impl ValidationProblem {
+  pub(crate) fn new(path: String, message: String) -> ValidationProblem {
+    ValidationProblem { path, message }
+  }
+}
+
+impl fmt::Display for ValidationProblem {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}: {}", self.path, self.message)
+  }
+}
+
+impl GameSpec {
+  /// Checks all cross-references between the items of the game spec.
+  ///
+  /// Returns all found problems, an empty list means the spec is valid.
+  pub fn validate(&self) -> Vec<ValidationProblem> {
+    self.check_references(&[], &[])
+  }
+
+  /// Checks the cross-references, the assets of the given ids are declared by the entries which
+  /// cannot be read, so the references to them are not reported.
+  fn check_references(
+    &self,
+    unread_fa: &[FungibleAssetId],
+    unread_nfa: &[NonFungibleClassId],
+  ) -> Vec<ValidationProblem> {
+    let mut problems = vec![];
+
+    // 1. Collect declared ids, duplicates make the id remapping ambiguous
+    let mut fa_ids = HashSet::new();
+    for (idx, (fa_id, _)) in self.fa.iter().enumerate() {
+      if !fa_ids.insert(*fa_id) {
+        problems.push(ValidationProblem::new(
+          format!("$.fa[{}][0]", idx),
+          format!("duplicate FA id {}", fa_id.0),
+        ));
+      }
+    }
+    let mut nfa_ids = HashSet::new();
+    for (idx, (class_id, _)) in self.nfa.iter().enumerate() {
+      if !nfa_ids.insert(*class_id) {
+        problems.push(ValidationProblem::new(
+          format!("$.nfa[{}][0]", idx),
+          format!("duplicate NFA class id {}", class_id.0),
+        ));
+      }
+    }
+    fa_ids.extend(unread_fa);
+    nfa_ids.extend(unread_nfa);
+    let check_fa = |problems: &mut Vec<ValidationProblem>, path: String, id: &FungibleAssetId| {
+      if !fa_ids.contains(id) {
+        problems.push(ValidationProblem::new(
+          path,
+          format!("FA {} not found in the spec", id.0),
+        ));
+      }
+    };
+    let check_nfa =
+      |problems: &mut Vec<ValidationProblem>, path: String, id: &NonFungibleClassId| {
+        if !nfa_ids.contains(id) {
+          problems.push(ValidationProblem::new(
+            path,
+            format!("NFA class {} not found in the spec", id.0),
+          ));
+        }
+      };
+
+    // 2. Members
+    let mut members = HashSet::new();
+    for (idx, member) in self.organization_members.iter().enumerate() {
+      if !members.insert(member) {
+        problems.push(ValidationProblem::new(
+          format!("$.organization_members[{}]", idx),
+          format!("duplicate member {}", member),
+        ));
+      }
+    }
+
+    // 3. Onboarding assets
+    if let Some(onboarding) = &self.organization_details.onboarding_assets {
+      for (idx, asset) in onboarding.0.iter().enumerate() {
+        let path = format!("$.organization_details.onboarding_assets[{}]", idx);
+        match asset {
+          AirDropAsset::Fa(id, _) => check_fa(&mut problems, format!("{}.Fa[0]", path), id),
+          AirDropAsset::Nfa(id, _) => check_nfa(&mut problems, format!("{}.Nfa[0]", path), id),
+        }
+      }
+    }
+
+    // 4. Characteristics of the NFA
+    for (idx, (_, details)) in self.nfa.iter().enumerate() {
+      if let Some(bettor) = &details.bettor {
+        for (w_idx, winning) in bettor.winnings.0.iter().enumerate() {
+          let path = format!("$.nfa[{}][1].bettor.winnings[{}]", idx, w_idx);
+          match winning {
+            BettorWinning::Fa(id, _) => check_fa(&mut problems, format!("{}.Fa[0]", path), id),
+            BettorWinning::Nfa(id) => check_nfa(&mut problems, format!("{}.Nfa", path), id),
+          }
+        }
+      }
+      if let Some(purchased) = &details.purchased {
+        for (o_idx, offer) in purchased.offers.0.iter().enumerate() {
+          let path = format!("$.nfa[{}][1].purchased.offers[{}].fa", idx, o_idx);
+          check_fa(&mut problems, path, &offer.fa);
+        }
+      }
+    }
+
+    // 5. Attributes
+    let mut attr_keys = HashSet::new();
+    for (idx, (class_id, key, _)) in self.attributes.iter().enumerate() {
+      check_nfa(&mut problems, format!("$.attributes[{}][0]", idx), class_id);
+      if !attr_keys.insert((class_id, &key.0)) {
+        problems.push(ValidationProblem::new(
+          format!("$.attributes[{}][1]", idx),
+          format!("duplicate attribute for NFA class {}", class_id.0),
+        ));
+      }
+    }
+
+    // 6. Balances
+    for (idx, (_, fa_id, _)) in self.balances.iter().flatten().enumerate() {
+      check_fa(&mut problems, format!("$.balances[{}][1]", idx), fa_id);
+    }
+
+    // 7. Instances
+    for (idx, instance) in self.instances.iter().flatten().enumerate() {
+      check_nfa(
+        &mut problems,
+        format!("$.instances[{}].class", idx),
+        &instance.class,
+      );
+    }
+
+    problems
+  }
+}
+
+/// Reads the spec file which cannot be read as a whole entry by entry.
+///
+/// Returns the problems of all entries which cannot be read with their paths, followed by the
+/// problems of the cross-references between the entries which are read.
+pub(crate) fn read_problems(content: &str, format: SpecFormat) -> Vec<ValidationProblem> {
+  let mut tree = match format.parse_tree(content) {
+    Ok(tree) => tree,
+    Err(e) => return vec![ValidationProblem::new("$".into(), e.to_string())],
+  };
+  match tree.get("spec_format_version").map(Value::as_u64) {
+    // the first version differs only by the name of the node version
+    None => {
+      if let Some(version) = tree.as_object_mut().and_then(|tree| tree.remove("version")) {
+        tree["node_version"] = version;
+      }
+    },
+    Some(Some(version)) if version <= SPEC_FORMAT_VERSION as u64 => {},
+    Some(_) => {
+      return vec![ValidationProblem::new(
+        "$.spec_format_version".into(),
+        format!(
+          "format version {} is not supported, the latest known is {}",
+          tree["spec_format_version"], SPEC_FORMAT_VERSION
+        ),
+      )]
+    },
+  }
+
+  let (handles, problems, declarations) = Handles::declared_leniently(&tree);
+  let mut reader = EntryReader {
+    tree: &tree,
+    problems,
+    declarations,
+    indices: HashMap::new(),
+  };
+  let (game_spec, unread_fa, unread_nfa) = handles.scope(|| {
+    let game_spec = GameSpec {
+      spec_format_version: SPEC_FORMAT_VERSION,
+      node_version: reader.field("node_version").unwrap_or_default(),
+      hash: reader.field("hash").unwrap_or_default(),
+      organization_details: reader
+        .field("organization_details")
+        .unwrap_or(OrganizationDetails {
+          name: BoundedVec(vec![]),
+          onboarding_assets: None,
+        }),
+      organization_members: reader.list("organization_members"),
+      fa: reader.list("fa"),
+      nfa: reader.list("nfa"),
+      attributes: reader.list("attributes"),
+      users: reader.optional_list("users"),
+      balances: reader.optional_list("balances"),
+      instances: reader.optional_list("instances"),
+      asset_ids: None,
+    };
+    // the assets of the entries which cannot be read are still declared
+    (game_spec, reader.unread_ids("fa"), reader.unread_ids("nfa"))
+  });
+  let EntryReader {
+    mut problems,
+    indices,
+    ..
+  } = reader;
+  // the paths of the read spec point to the entries of the tree
+  problems.extend(
+    game_spec
+      .check_references(&unread_fa, &unread_nfa)
+      .into_iter()
+      .map(|problem| ValidationProblem::new(entry_path(problem.path, &indices), problem.message)),
+  );
+  problems
+}
+
+/// Reads the entries of the spec tree one by one and records the problems of the invalid ones
+struct EntryReader<'a> {
+  tree: &'a Value,
+  problems: Vec<ValidationProblem>,
+  /// Indices of the asset entries with the invalid declarations, which are already reported
+  declarations: HashMap<&'static str, HashSet<usize>>,
+  /// Indices of the read entries in the lists of the tree by the names of the lists
+  indices: HashMap<&'static str, Vec<usize>>,
+}
+
+impl EntryReader<'_> {
+  fn read<T: DeserializeOwned>(&mut self, value: &Value, mut path: String) -> Option<T> {
+    match serde_path_to_error::deserialize(value) {
+      Ok(value) => Some(value),
+      Err(e) => {
+        for segment in e.path() {
+          let _ = match segment {
+            Segment::Seq { index } => write!(path, "[{}]", index),
+            Segment::Map { key } | Segment::Enum { variant: key } => write!(path, ".{}", key),
+            Segment::Unknown => write!(path, ".?"),
+          };
+        }
+        self
+          .problems
+          .push(ValidationProblem::new(path, e.into_inner().to_string()));
+        None
+      },
+    }
+  }
+
+  fn field<T: DeserializeOwned>(&mut self, name: &str) -> Option<T> {
+    match self.tree.get(name) {
+      Some(value) => self.read(value, format!("$.{}", name)),
+      None => {
+        self.missing(name);
+        None
+      },
+    }
+  }
+
+  fn list<T: DeserializeOwned>(&mut self, name: &'static str) -> Vec<T> {
+    if self.tree.get(name).is_none() {
+      self.missing(name);
+    }
+    self.optional_list(name).unwrap_or_default()
+  }
+
+  /// Reads the entries of the list which may be absent, the invalid entries are skipped
+  fn optional_list<T: DeserializeOwned>(&mut self, name: &'static str) -> Option<Vec<T>> {
+    let value = self.tree.get(name).filter(|value| !value.is_null())?;
+    let Some(entries) = value.as_array() else {
+      self.problems.push(ValidationProblem::new(
+        format!("$.{}", name),
+        "invalid type, expected a sequence".to_string(),
+      ));
+      return None;
+    };
+    let mut items = vec![];
+    let mut indices = vec![];
+    let declarations = self.declarations.remove(name).unwrap_or_default();
+    for (idx, entry) in entries.iter().enumerate() {
+      if declarations.contains(&idx) {
+        continue;
+      }
+      if let Some(item) = self.read(entry, format!("$.{}[{}]", name, idx)) {
+        items.push(item);
+        indices.push(idx);
+      }
+    }
+    self.indices.insert(name, indices);
+    Some(items)
+  }
+
+  /// Ids of the entries of the asset list which cannot be read, if their keys are valid
+  fn unread_ids<T: DeserializeOwned>(&self, name: &str) -> Vec<T> {
+    let read = self.indices.get(name);
+    self
+      .tree
+      .get(name)
+      .and_then(Value::as_array)
+      .into_iter()
+      .flatten()
+      .enumerate()
+      .filter(|(idx, _)| !read.is_some_and(|read| read.contains(idx)))
+      .filter_map(|(_, entry)| T::deserialize(entry.get(0)?).ok())
+      .collect()
+  }
+
+  fn missing(&mut self, name: &str) {
+    self.problems.push(ValidationProblem::new(
+      "$".into(),
+      format!("missing field `{}`", name),
+    ));
+  }
+}
+
+/// Replaces the index of the list entry in the path with the index of the entry in the tree
+fn entry_path(path: String, indices: &HashMap<&'static str, Vec<usize>>) -> String {
+  for (name, indices) in indices {
+    let prefix = format!("$.{}[", name);
+    let Some((idx, rest)) = path
+      .strip_prefix(&prefix)
+      .and_then(|rest| rest.split_once(']'))
+    else {
+      continue;
+    };
+    if let Some(idx) = idx.parse::<usize>().ok().and_then(|idx| indices.get(idx)) {
+      return format!("{}{}]{}", prefix, idx, rest);
+    }
+  }
+  path
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use super::*;
+  use crate::game_spec::tests::game_spec;
+
+  fn paths(game_spec: &GameSpec) -> Vec<String> {
+    game_spec
+      .validate()
+      .into_iter()
+      .map(|problem| problem.path)
+      .collect()
+  }
+
+  #[test]
+  fn valid_spec_has_no_problems() {
+    assert_eq!(game_spec(|_| {}).validate(), vec![]);
+  }
+
+  #[test]
+  fn duplicate_ids_are_reported() {
+    let game_spec = game_spec(|content| {
+      content["fa"] = json!([[0, content["fa"][0][1]], [0, content["fa"][1][1]]]);
+      content["nfa"] = json!([[3, content["nfa"][0][1]], [3, content["nfa"][1][1]]]);
+      content["organization_details"]["onboarding_assets"] = json!(null);
+      content["nfa"][0][1]["purchased"] = json!(null);
+      content["attributes"] = json!([]);
+    });
+    let problems = game_spec.validate();
+    assert_eq!(
+      problems,
+      vec![
+        ValidationProblem::new("$.fa[1][0]".into(), "duplicate FA id 0".into()),
+        ValidationProblem::new("$.nfa[1][0]".into(), "duplicate NFA class id 3".into()),
+      ]
+    );
+  }
+
+  #[test]
+  fn duplicate_member_is_reported() {
+    let game_spec = game_spec(|content| {
+      let member = content["organization_members"][0].clone();
+      content["organization_members"] = json!([member, member]);
+    });
+    assert_eq!(paths(&game_spec), vec!["$.organization_members[1]"]);
+  }
+
+  #[test]
+  fn dangling_references_are_reported() {
+    let game_spec = game_spec(|content| {
+      content["organization_details"]["onboarding_assets"] =
+        json!([{ "Fa": [7, 5] }, { "Nfa": [8, []] }]);
+      content["nfa"][0][1]["purchased"]["offers"][0]["fa"] = json!(7);
+      content["nfa"][1][1]["bettor"] = json!({
+        "outcomes": [{ "name": "win", "probability": 100, "result": "Win" }],
+        "winnings": [{ "Fa": [7, 1] }, { "Nfa": 8 }],
+        "rounds": 1,
+        "draw_outcome": "Keep",
+      });
+    });
+    let problems = game_spec.validate();
+    assert_eq!(
+      problems.iter().map(ToString::to_string).collect::<Vec<_>>(),
+      vec![
+        "$.organization_details.onboarding_assets[0].Fa[0]: FA 7 not found in the spec",
+        "$.organization_details.onboarding_assets[1].Nfa[0]: NFA class 8 not found in the spec",
+        "$.nfa[0][1].purchased.offers[0].fa: FA 7 not found in the spec",
+        "$.nfa[1][1].bettor.winnings[0].Fa[0]: FA 7 not found in the spec",
+        "$.nfa[1][1].bettor.winnings[1].Nfa: NFA class 8 not found in the spec",
+      ]
+    );
+  }
+
+  #[test]
+  fn attribute_problems_are_reported() {
+    let game_spec = game_spec(|content| {
+      content["attributes"] = json!([
+        ["sword", "damage", { "Text": "high" }],
+        ["sword", "damage", { "Text": "low" }],
+        ["shield", "damage", { "Text": "low" }],
+        [8, "weight", { "Text": "heavy" }],
+      ]);
+    });
+    assert_eq!(
+      paths(&game_spec),
+      vec!["$.attributes[1][1]", "$.attributes[3][0]"]
+    );
+  }
+
+  #[test]
+  fn balances_and_instances_are_checked() {
+    let owner = "5FLSigC9HGRKVhB9FiEo4Y3koPsNmBmLJbpXg2mp1hXcS59Y";
+    let game_spec = game_spec(|content| {
+      content["balances"] = json!([[owner, "gold", 5], [owner, 7, 1]]);
+      content["instances"] = json!([
+        { "class": "sword", "asset": 0, "owner": owner, "locked": false, "attributes": [] },
+        { "class": 8, "asset": 0, "owner": owner, "locked": false, "attributes": [] },
+      ]);
+    });
+    assert_eq!(
+      paths(&game_spec),
+      vec!["$.balances[1][1]", "$.instances[1].class"]
+    );
+  }
+
+  /// Problems of the test fixture edited by `edit`, which is read entry by entry
+  fn read_fixture_problems(edit: impl FnOnce(&mut Value)) -> Vec<String> {
+    let mut content: Value =
+      serde_json::from_str(include_str!("../tests/fixtures/game_spec.json")).unwrap();
+    edit(&mut content);
+    read_problems(&content.to_string(), SpecFormat::Json)
+      .iter()
+      .map(ToString::to_string)
+      .collect()
+  }
+
+  #[test]
+  fn unreadable_entries_are_reported_together() {
+    let problems = read_fixture_problems(|content| {
+      content["fa"][1][0] = json!("gold");
+      content["organization_details"]["onboarding_assets"][0]["Fa"][0] = json!("silver");
+      content["nfa"][0][1]["purchased"]["offers"][0]["price"] = json!("ten");
+      content["attributes"][1][0] = json!("axe");
+    });
+    assert_eq!(
+      problems,
+      vec![
+        "$.fa[1][0]: Handle gold is declared more than once",
+        "$.organization_details.onboarding_assets[0].Fa[0]: unknown FA handle silver",
+        "$.nfa[0][1].purchased.offers[0].price: invalid type: string \"ten\", expected u128",
+        "$.attributes[1][0]: unknown NFA handle axe",
+      ]
+    );
+  }
+
+  #[test]
+  fn references_of_read_entries_are_checked() {
+    let problems = read_fixture_problems(|content| {
+      // the sword cannot be read, but it's declared
+      content["nfa"][0][1]["instances"] = json!(-1);
+      content["attributes"][1][0] = json!(8);
+    });
+    assert_eq!(
+      problems,
+      vec![
+        "$.nfa[0][1].instances: invalid value: integer `-1`, expected u32",
+        "$.attributes[1][0]: NFA class 8 not found in the spec",
+      ]
+    );
+  }
+
+  #[test]
+  fn yaml_spec_is_read_entry_by_entry() {
+    let game_spec = game_spec(|_| {});
+    let content = crate::migration::write_game_spec(&game_spec, SpecFormat::Yaml)
+      .unwrap()
+      .replace("speed: 1", "speed: fast");
+    assert_eq!(
+      read_problems(&content, SpecFormat::Yaml)
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>(),
+      vec!["$.fa[1][1].top_upped.speed: invalid type: string \"fast\", expected u128"]
+    );
+  }
+}
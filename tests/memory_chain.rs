@@ -0,0 +1,615 @@
+use finalbiome_impex::{
+  apply_game, export_game, import_game, teardown_game, BlockAt, BoundedVec, ChainBackend,
+  ExportOptions, GameSpec, ImpexError, ImportOptions, MemoryChain, Rejection, SpecSource,
+  SubmitOptions, TeardownOptions,
+};
+use sp_core::{sr25519, Pair};
+use sp_runtime::AccountId32;
+use std::{path::PathBuf, time::Duration};
+
+const ORGANIZATION_SEED: &str = "//Alice";
+const MANAGER_SEED: &str = "//Bob";
+
+async fn load_spec() -> GameSpec {
+  let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/game_spec.json");
+  SpecSource::File(path)
+    .load()
+    .await
+    .expect("fixture is valid")
+}
+
+fn organization() -> AccountId32 {
+  account(ORGANIZATION_SEED)
+}
+
+fn account(seed: &str) -> AccountId32 {
+  sr25519::Pair::from_string(seed, None)
+    .expect("valid seed")
+    .public()
+    .into()
+}
+
+#[tokio::test]
+async fn import_then_export_round_trip() {
+  let chain = MemoryChain::new();
+  let spec = load_spec().await;
+
+  let report = import_game(
+    &chain,
+    &spec,
+    ORGANIZATION_SEED,
+    MANAGER_SEED,
+    &ImportOptions::default(),
+  )
+  .await
+  .unwrap();
+  assert_eq!(report.fa_ids_map.len(), 2);
+  assert_eq!(report.nfa_ids_map.len(), 2);
+
+  let exported = export_game(&chain, &organization(), &ExportOptions::default())
+    .await
+    .unwrap();
+  let diff = spec.diff(&exported);
+  assert!(diff.is_empty(), "{}", diff);
+  assert_eq!(exported.fa.len(), 2);
+}
+
+#[tokio::test]
+async fn import_of_existing_game_submits_nothing() {
+  let chain = MemoryChain::new();
+  let spec = load_spec().await;
+
+  import_game(
+    &chain,
+    &spec,
+    ORGANIZATION_SEED,
+    MANAGER_SEED,
+    &ImportOptions::default(),
+  )
+  .await
+  .unwrap();
+  let best = chain.best_number();
+  let report = import_game(
+    &chain,
+    &spec,
+    ORGANIZATION_SEED,
+    MANAGER_SEED,
+    &ImportOptions::default(),
+  )
+  .await
+  .unwrap();
+  assert!(report.steps.is_empty());
+  assert_eq!(chain.best_number(), best);
+}
+
+#[tokio::test]
+async fn players_receive_onboarding_assets() {
+  let chain = MemoryChain::new();
+  let spec = load_spec().await;
+
+  import_game(
+    &chain,
+    &spec,
+    ORGANIZATION_SEED,
+    MANAGER_SEED,
+    &ImportOptions {
+      players: Some("//Player/{n}".to_string()),
+      ..Default::default()
+    },
+  )
+  .await
+  .unwrap();
+
+  let options = ExportOptions {
+    include_users: true,
+    include_balances: true,
+    include_instances: true,
+    ..Default::default()
+  };
+  let exported = export_game(&chain, &organization(), &options)
+    .await
+    .unwrap();
+  let users = exported.users.unwrap();
+  assert_eq!(users.len(), 2);
+  let balances = exported.balances.unwrap();
+  assert_eq!(balances.len(), 2);
+  assert!(balances.iter().all(|(_, _, balance)| balance.0 == 5));
+  // the instances get the attributes of the class
+  let instances = exported.instances.unwrap();
+  assert_eq!(instances.len(), 2);
+  assert!(instances
+    .iter()
+    .all(|instance| users.contains(&instance.owner) && instance.attributes.len() == 2));
+}
+
+#[tokio::test]
+async fn state_is_not_imported_silently() {
+  let chain = MemoryChain::new();
+  let spec = load_spec().await;
+  let players = ImportOptions {
+    players: Some("//Player/{n}".to_string()),
+    ..Default::default()
+  };
+  import_game(&chain, &spec, ORGANIZATION_SEED, MANAGER_SEED, &players)
+    .await
+    .unwrap();
+  let options = ExportOptions {
+    include_users: true,
+    include_balances: true,
+    include_instances: true,
+    ..Default::default()
+  };
+  let exported = export_game(&chain, &organization(), &options)
+    .await
+    .unwrap();
+
+  let chain = MemoryChain::new();
+  for (skip_balances, skip_instances) in [(false, false), (true, false), (false, true)] {
+    let options = ImportOptions {
+      skip_balances,
+      skip_instances,
+      ..players.clone()
+    };
+    let result = import_game(&chain, &exported, ORGANIZATION_SEED, MANAGER_SEED, &options).await;
+    assert!(matches!(result, Err(ImpexError::Spec(_))), "{:?}", result);
+  }
+  assert_eq!(chain.best_number(), 0);
+
+  let options = ImportOptions {
+    skip_balances: true,
+    skip_instances: true,
+    ..players
+  };
+  import_game(&chain, &exported, ORGANIZATION_SEED, MANAGER_SEED, &options)
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn export_at_earlier_block() {
+  let chain = MemoryChain::new();
+  let spec = load_spec().await;
+
+  import_game(
+    &chain,
+    &spec,
+    ORGANIZATION_SEED,
+    MANAGER_SEED,
+    &ImportOptions::default(),
+  )
+  .await
+  .unwrap();
+
+  // block 1 holds the created organization only
+  let options = ExportOptions {
+    at: BlockAt::Number(1),
+    ..Default::default()
+  };
+  let exported = export_game(&chain, &organization(), &options)
+    .await
+    .unwrap();
+  assert_eq!(exported.organization_details.name.0, b"Game".to_vec());
+  assert!(exported.organization_members.is_empty());
+  assert!(exported.fa.is_empty());
+  assert!(exported.nfa.is_empty());
+
+  let options = ExportOptions {
+    at: BlockAt::Number(1000),
+    ..Default::default()
+  };
+  let result = export_game(&chain, &organization(), &options).await;
+  assert!(matches!(result, Err(ImpexError::NotFound(_))));
+}
+
+#[tokio::test]
+async fn rejected_call_returns_pallet_error() {
+  let chain = MemoryChain::new();
+  let mut spec = load_spec().await;
+  spec.fa[0].1.name = BoundedVec(b"A name which is longer than the limit".to_vec());
+
+  let result = import_game(
+    &chain,
+    &spec,
+    ORGANIZATION_SEED,
+    MANAGER_SEED,
+    &ImportOptions::default(),
+  )
+  .await;
+  match result {
+    Err(
+      ref e @ ImpexError::Dispatch {
+        ref pallet,
+        ref error,
+        ..
+      },
+    ) => {
+      assert_eq!(pallet, "FungibleAssets");
+      assert_eq!(error, "AssetNameTooLong");
+      // the call fails the same way again
+      assert!(!e.is_retryable());
+    },
+    _ => panic!("expected the dispatch error, got {:?}", result),
+  }
+}
+
+#[tokio::test]
+async fn pipelined_import_matches_sequential_one() {
+  let spec = load_spec().await;
+  let mut exported = vec![];
+  for window in [1, 16] {
+    let chain = MemoryChain::new();
+    let options = ImportOptions {
+      submit: SubmitOptions {
+        window,
+        ..Default::default()
+      },
+      ..Default::default()
+    };
+    let report = import_game(&chain, &spec, ORGANIZATION_SEED, MANAGER_SEED, &options)
+      .await
+      .unwrap();
+    // each submitted call uses the next nonce of its signer
+    let organization_nonce = chain.account_nonce(&organization()).await.unwrap();
+    let manager: AccountId32 = sr25519::Pair::from_string(MANAGER_SEED, None)
+      .expect("valid seed")
+      .public()
+      .into();
+    let manager_nonce = chain.account_nonce(&manager).await.unwrap();
+    assert_eq!(
+      (organization_nonce + manager_nonce) as usize,
+      report.steps.len()
+    );
+    exported.push(
+      export_game(&chain, &organization(), &ExportOptions::default())
+        .await
+        .unwrap(),
+    );
+  }
+  let diff = exported[0].diff(&exported[1]);
+  assert!(diff.is_empty(), "{}", diff);
+}
+
+#[tokio::test]
+async fn batched_import_uses_fewer_blocks() {
+  let spec = load_spec().await;
+  let sequential = MemoryChain::new();
+  import_game(
+    &sequential,
+    &spec,
+    ORGANIZATION_SEED,
+    MANAGER_SEED,
+    &ImportOptions::default(),
+  )
+  .await
+  .unwrap();
+  let batched = MemoryChain::new().with_utility();
+  let report = import_game(
+    &batched,
+    &spec,
+    ORGANIZATION_SEED,
+    MANAGER_SEED,
+    &ImportOptions::default(),
+  )
+  .await
+  .unwrap();
+  assert!(batched.best_number() < sequential.best_number());
+  assert_eq!(report.steps.len() as u64, sequential.best_number());
+
+  let exported = export_game(&batched, &organization(), &ExportOptions::default())
+    .await
+    .unwrap();
+  let diff = spec.diff(&exported);
+  assert!(diff.is_empty(), "{}", diff);
+  assert_eq!(exported.fa.len(), 2);
+}
+
+#[tokio::test]
+async fn failed_batch_dispatches_nothing() {
+  let mut spec = load_spec().await;
+  // one member more than the runtime allows
+  for seed in ["//Charlie", "//Dave", "//Eve"] {
+    let member: AccountId32 = sr25519::Pair::from_string(seed, None)
+      .expect("valid seed")
+      .public()
+      .into();
+    spec.organization_members.push(member);
+  }
+
+  for (chain, members) in [
+    (MemoryChain::new(), 3),
+    (MemoryChain::new().with_utility(), 0),
+  ] {
+    let result = import_game(
+      &chain,
+      &spec,
+      ORGANIZATION_SEED,
+      MANAGER_SEED,
+      &ImportOptions::default(),
+    )
+    .await;
+    assert!(
+      matches!(&result, Err(ImpexError::Dispatch { error, .. }) if error == "MembershipLimitReached"),
+      "expected the dispatch error, got {:?}",
+      result
+    );
+    let exported = export_game(&chain, &organization(), &ExportOptions::default())
+      .await
+      .unwrap();
+    assert_eq!(exported.organization_members.len(), members);
+  }
+}
+
+#[tokio::test]
+async fn teardown_removes_imported_game() {
+  let chain = MemoryChain::new();
+  let spec = load_spec().await;
+  import_game(
+    &chain,
+    &spec,
+    ORGANIZATION_SEED,
+    MANAGER_SEED,
+    &ImportOptions::default(),
+  )
+  .await
+  .unwrap();
+
+  // without the organization seed the members are kept
+  let report = teardown_game(
+    &chain,
+    &organization(),
+    MANAGER_SEED,
+    &TeardownOptions::default(),
+  )
+  .await
+  .unwrap();
+  assert!(!report.steps.is_empty());
+  let exported = export_game(&chain, &organization(), &ExportOptions::default())
+    .await
+    .unwrap();
+  assert!(exported.fa.is_empty());
+  assert!(exported.nfa.is_empty());
+  assert!(exported.attributes.is_empty());
+  assert!(exported.organization_details.onboarding_assets.is_none());
+  assert_eq!(exported.organization_members.len(), 1);
+
+  let options = TeardownOptions {
+    organization_seed: Some(ORGANIZATION_SEED.to_string()),
+    ..Default::default()
+  };
+  teardown_game(&chain, &organization(), MANAGER_SEED, &options)
+    .await
+    .unwrap();
+  let exported = export_game(&chain, &organization(), &ExportOptions::default())
+    .await
+    .unwrap();
+  assert!(exported.organization_members.is_empty());
+}
+
+#[tokio::test]
+async fn apply_updates_game_to_spec() {
+  let chain = MemoryChain::new();
+  let mut spec = load_spec().await;
+  spec.organization_members.push(account("//Dave"));
+  import_game(
+    &chain,
+    &spec,
+    ORGANIZATION_SEED,
+    MANAGER_SEED,
+    &ImportOptions::default(),
+  )
+  .await
+  .unwrap();
+  let fa_id = |game_spec: &GameSpec, name: &[u8]| {
+    game_spec
+      .fa
+      .iter()
+      .find(|(_, details)| details.name.0 == name)
+      .map(|(id, _)| *id)
+      .unwrap()
+  };
+  let imported = export_game(&chain, &organization(), &ExportOptions::default())
+    .await
+    .unwrap();
+  let old_gold = fa_id(&imported, b"Gold");
+
+  // the member and the attribute are removed, gold is changed
+  let mut spec = load_spec().await;
+  spec.attributes.retain(|(_, key, _)| key.0 != b"rarity");
+  if let Some(cup) = &mut spec.fa[0].1.cup_global {
+    cup.amount.0 = 30;
+  }
+  apply_game(
+    &chain,
+    &spec,
+    ORGANIZATION_SEED,
+    MANAGER_SEED,
+    &SubmitOptions::default(),
+  )
+  .await
+  .unwrap();
+
+  let exported = export_game(&chain, &organization(), &ExportOptions::default())
+    .await
+    .unwrap();
+  let diff = spec.diff(&exported);
+  assert!(diff.is_empty(), "{}", diff);
+  assert!(!exported.organization_members.contains(&account("//Dave")));
+  assert_eq!(exported.attributes.len(), 1);
+  // the changed FA is created again and the old one is destroyed
+  assert_eq!(exported.fa.len(), 2);
+  let gold = fa_id(&exported, b"Gold");
+  assert_ne!(gold, old_gold);
+  assert!(exported.fa.iter().all(|(id, _)| *id != old_gold));
+  // the offer of the sword refers to the new FA
+  let sword = exported
+    .nfa
+    .iter()
+    .find(|(_, details)| details.name.0 == b"Sword")
+    .map(|(_, details)| details)
+    .unwrap();
+  assert_eq!(sword.purchased.as_ref().unwrap().offers.0[0].fa, gold);
+
+  // the game is up to date
+  let best = chain.best_number();
+  let report = apply_game(
+    &chain,
+    &spec,
+    ORGANIZATION_SEED,
+    MANAGER_SEED,
+    &SubmitOptions::default(),
+  )
+  .await
+  .unwrap();
+  assert!(report.steps.is_empty());
+  assert_eq!(chain.best_number(), best);
+}
+
+#[tokio::test]
+async fn failed_import_is_torn_down() {
+  let chain = MemoryChain::new();
+  let mut spec = load_spec().await;
+  spec.fa[1].1.name = BoundedVec(b"A name which is longer than the limit".to_vec());
+
+  let options = ImportOptions {
+    teardown_on_failure: true,
+    ..Default::default()
+  };
+  let result = import_game(&chain, &spec, ORGANIZATION_SEED, MANAGER_SEED, &options).await;
+  assert!(matches!(result, Err(ImpexError::Dispatch { .. })));
+
+  // the organization stays, the items created before the failure are removed
+  let exported = export_game(&chain, &organization(), &ExportOptions::default())
+    .await
+    .unwrap();
+  assert!(exported.fa.is_empty());
+  assert!(exported.nfa.is_empty());
+  assert!(exported.organization_members.is_empty());
+}
+
+#[tokio::test]
+async fn failed_teardown_keeps_import_error() {
+  let mut spec = load_spec().await;
+  spec.fa[1].1.name = BoundedVec(b"A name which is longer than the limit".to_vec());
+  let mut options = ImportOptions {
+    submit: SubmitOptions {
+      window: 1,
+      batch_size: 1,
+      retries: 0,
+      ..Default::default()
+    },
+    ..Default::default()
+  };
+
+  // count the calls submitted by the import until it fails
+  let chain = MemoryChain::new();
+  let result = import_game(&chain, &spec, ORGANIZATION_SEED, MANAGER_SEED, &options).await;
+  assert!(matches!(result, Err(ImpexError::Dispatch { .. })));
+  let submitted = chain.best_number() as usize + 1;
+
+  // the calls of the teardown are dropped by the pool
+  let chain = MemoryChain::new();
+  chain.drop_transactions_after(submitted, usize::MAX);
+  options.teardown_on_failure = true;
+  let result = import_game(&chain, &spec, ORGANIZATION_SEED, MANAGER_SEED, &options).await;
+  assert!(
+    matches!(result, Err(ImpexError::Dispatch { .. })),
+    "expected the error of the import, got {:?}",
+    result
+  );
+  // nothing is torn down
+}
+
+#[tokio::test]
+async fn dropped_transactions_are_retried() {
+  let spec = load_spec().await;
+  let submit = SubmitOptions {
+    backoff: Duration::from_millis(1),
+    ..Default::default()
+  };
+
+  let chain = MemoryChain::new();
+  chain.drop_transactions(2);
+  let options = ImportOptions {
+    submit: submit.clone(),
+    ..Default::default()
+  };
+  import_game(&chain, &spec, ORGANIZATION_SEED, MANAGER_SEED, &options)
+    .await
+    .unwrap();
+  let exported = export_game(&chain, &organization(), &ExportOptions::default())
+    .await
+    .unwrap();
+  let diff = spec.diff(&exported);
+  assert!(diff.is_empty(), "{}", diff);
+
+  let chain = MemoryChain::new();
+  chain.drop_transactions(1);
+  let options = ImportOptions {
+    submit: SubmitOptions {
+      retries: 0,
+      ..submit
+    },
+    ..Default::default()
+  };
+  let result = import_game(&chain, &spec, ORGANIZATION_SEED, MANAGER_SEED, &options).await;
+  match result {
+    Err(e @ ImpexError::Rejected(Rejection::Dropped)) => assert!(e.is_retryable()),
+    _ => panic!("expected the rejected transaction, got {:?}", result),
+  }
+}
+
+#[tokio::test]
+async fn outdated_transactions_are_not_retried() {
+  let chain = MemoryChain::new();
+  let spec = load_spec().await;
+  chain.outdate_transactions(1);
+  let options = ImportOptions {
+    submit: SubmitOptions {
+      backoff: Duration::from_millis(1),
+      ..Default::default()
+    },
+    ..Default::default()
+  };
+  // the nonce may be used by the transaction itself, so it is not submitted with another one
+  let result = import_game(&chain, &spec, ORGANIZATION_SEED, MANAGER_SEED, &options).await;
+  assert!(
+    matches!(result, Err(ImpexError::Rejected(Rejection::Stale))),
+    "expected the outdated transaction, got {:?}",
+    result
+  );
+  assert_eq!(chain.best_number(), 0);
+}
+
+#[tokio::test]
+async fn included_transaction_which_timed_out_is_not_submitted_again() {
+  let chain = MemoryChain::new();
+  let spec = load_spec().await;
+  // the organization and its member are added, gold is created, but its inclusion is reported
+  // after the timeout
+  chain.delay_transactions_after(2, 1, Duration::from_millis(500));
+  let options = ImportOptions {
+    submit: SubmitOptions {
+      backoff: Duration::from_millis(1),
+      timeout: Duration::from_millis(100),
+      ..Default::default()
+    },
+    ..Default::default()
+  };
+  let result = import_game(&chain, &spec, ORGANIZATION_SEED, MANAGER_SEED, &options).await;
+  assert!(
+    matches!(result, Err(ImpexError::Unconfirmed(_))),
+    "expected the unconfirmed transaction, got {:?}",
+    result
+  );
+
+  // the import planned again finds the organization and creates the rest of the game
+  import_game(&chain, &spec, ORGANIZATION_SEED, MANAGER_SEED, &options)
+    .await
+    .unwrap();
+  let exported = export_game(&chain, &organization(), &ExportOptions::default())
+    .await
+    .unwrap();
+  let diff = spec.diff(&exported);
+  assert!(diff.is_empty(), "{}", diff);
+  assert_eq!(exported.fa.len(), 2);
+}